@@ -0,0 +1,371 @@
+use std::{fs, sync::Mutex, time::Duration};
+
+use anyhow::{anyhow, bail, Context};
+use client::{
+    AgentClient, AgentCreateDto, AgentCreatedDto, CheckTaskRequestDto, Client,
+    CodeSubmissionRequestDto, TaskExampleDto, TaskTypeDto, TasksDto,
+};
+use solver::Solver;
+
+use crate::{
+    config::{AgentConfig, Config},
+    llm::{self, LlmProvider, Message},
+    prompt,
+    repair::{self, Failure, RepairStats, Run, Unrepaired},
+    routing::{self, RoutingStats},
+    vote::{Ballot, Candidate},
+};
+
+/// Solves the tasks of the backend: prompts the LLM, runs the code it writes with `solver-rs` and
+/// submits the output.
+pub struct Agent {
+    client: Client,
+    credentials: AgentCreatedDto,
+    /// The ladder of providers, the cheapest first.
+    tiers: Vec<Box<dyn LlmProvider>>,
+    solver: Solver,
+    repair_stats: Mutex<RepairStats>,
+    routing_stats: Mutex<RoutingStats>,
+    config: Config,
+}
+
+impl Agent {
+    /// Continues as the agent in the credentials file, or creates a new agent and stores it there.
+    pub async fn new(config: Config) -> anyhow::Result<Self> {
+        let tiers = [&config.llm]
+            .into_iter()
+            .chain(&config.routing.ladder)
+            .map(llm::provider)
+            .collect::<anyhow::Result<_>>()?;
+        Self::with_providers(config, tiers).await
+    }
+
+    /// Like `new`, but with another provider than the ones in the config and no escalation.
+    pub async fn with_provider(config: Config, llm: Box<dyn LlmProvider>) -> anyhow::Result<Self> {
+        Self::with_providers(config, vec![llm]).await
+    }
+
+    /// Like `new`, but with another ladder of providers than the one in the config.
+    pub async fn with_providers(
+        config: Config,
+        tiers: Vec<Box<dyn LlmProvider>>,
+    ) -> anyhow::Result<Self> {
+        if tiers.is_empty() {
+            bail!("no LLM provider");
+        }
+        let client = Client::new(&config.backend.url, &config.backend.user_token);
+        let credentials = credentials(&client, &config.agent).await?;
+        let solver = Solver::new(&config.solver.dir);
+        let repair_stats = RepairStats::load(&config.repair.stats_file)
+            .with_context(|| format!("invalid {}", config.repair.stats_file.display()))?;
+        let routing_stats = RoutingStats::load(&config.routing.stats_file)
+            .with_context(|| format!("invalid {}", config.routing.stats_file.display()))?;
+
+        Ok(Self {
+            client,
+            credentials,
+            tiers,
+            solver,
+            repair_stats: Mutex::new(repair_stats),
+            routing_stats: Mutex::new(routing_stats),
+            config,
+        })
+    }
+
+    fn api(&self) -> AgentClient<'_> {
+        self.client.agent(self.credentials)
+    }
+
+    /// Tries every task that is not completed yet, up to `attempts_per_task` times, starting on
+    /// the cheapest tier and escalating to the next one as configured in `[routing]`.
+    pub async fn run(&self) -> anyhow::Result<()> {
+        for task in self.api().read_all_tasks().await? {
+            if task.completed {
+                println!("Task already completed: {}", task.name);
+                continue;
+            }
+
+            let models: Vec<&str> = self.tiers.iter().map(|llm| llm.model()).collect();
+            let route = routing::climb(
+                &self.config.routing,
+                &task.name,
+                &models,
+                self.config.agent.attempts_per_task,
+                |tier| self.solve(&task, self.tiers[tier].as_ref()),
+            )
+            .await;
+
+            let repair_stats = self.repair_stats.lock().unwrap();
+            repair_stats.save(&self.config.repair.stats_file)?;
+            let mut routing_stats = self.routing_stats.lock().unwrap();
+            routing_stats.tasks.insert(task.id.to_string(), route);
+            routing_stats.save(&self.config.routing.stats_file)?;
+        }
+
+        println!("Repairs (succeeded/attempted):");
+        for (kind, count) in &self.repair_stats.lock().unwrap().kinds {
+            println!("  {kind}: {}/{}", count.succeeded, count.attempted);
+        }
+        println!("Tasks solved per tier:");
+        let solved = self.routing_stats.lock().unwrap().solved_per_tier();
+        for (tier, llm) in self.tiers.iter().enumerate() {
+            let solved = solved.get(&tier).copied().unwrap_or_default();
+            println!("  {tier} ({}): {solved}", llm.model());
+        }
+        Ok(())
+    }
+
+    /// One attempt at a task with the provider of a tier, returns whether the solution was
+    /// correct.
+    pub async fn solve(&self, task: &TasksDto, llm: &dyn LlmProvider) -> anyhow::Result<bool> {
+        let task_dto = self.api().read_task(task.id).await?;
+        match task_dto.task_type {
+            TaskTypeDto::SimpleTask { description } => {
+                let response = self
+                    .complete(llm, &prompt::simple_task(&description))
+                    .await?;
+                let solution = extract::answer(&response).context("no answer in the response")?;
+                self.check(task, solution, None).await
+            }
+            TaskTypeDto::AdventOfCodePartOne {
+                description,
+                input,
+                examples,
+            } => {
+                self.solve_with_code(task, llm, &description, &input, &examples, false)
+                    .await
+            }
+            TaskTypeDto::AdventOfCodePartTwo {
+                description,
+                input,
+                examples,
+            } => {
+                self.solve_with_code(task, llm, &description, &input, &examples, true)
+                    .await
+            }
+            TaskTypeDto::CodeSubmission {
+                description,
+                examples,
+                ..
+            } => {
+                let examples = part_examples(&examples, false);
+                let candidate = self
+                    .write_code(llm, &description, false, None, &examples)
+                    .await?;
+                let dto = CodeSubmissionRequestDto {
+                    code: candidate.code,
+                    runtime: None,
+                };
+                let response = self.api().submit_code(task.id, &dto).await?;
+                if let Some(compile_error) = response.compile_error {
+                    println!("Compile error: {compile_error}");
+                }
+                Ok(response.correct)
+            }
+        }
+    }
+
+    /// Writes up to `candidates` programs and submits the output a quorum of them agrees on, or the
+    /// output of the program with the most confidence.
+    async fn solve_with_code(
+        &self,
+        task: &TasksDto,
+        llm: &dyn LlmProvider,
+        description: &str,
+        input: &str,
+        examples: &[TaskExampleDto],
+        part_two: bool,
+    ) -> anyhow::Result<bool> {
+        let examples = part_examples(examples, part_two);
+        let vote = &self.config.vote;
+        let mut ballot = Ballot::new(examples.len());
+        let mut last_error = None;
+        for candidate in 1..=vote.candidates {
+            if vote.candidates > 1 {
+                println!("Writing candidate {candidate}/{}", vote.candidates);
+            }
+            match self
+                .write_code(llm, description, part_two, Some(input), &examples)
+                .await
+            {
+                Ok(candidate) => {
+                    println!("Output: {}", candidate.output);
+                    if !examples.is_empty() {
+                        println!(
+                            "Examples passed: {}/{}",
+                            candidate.examples_passed,
+                            examples.len()
+                        );
+                    }
+                    ballot.push(candidate);
+                }
+                Err(error) => {
+                    if vote.candidates > 1 {
+                        println!("Error: {error:#}");
+                    }
+                    last_error = Some(error);
+                }
+            }
+
+            if let Some(winner) = ballot.winner(vote.quorum) {
+                if vote.candidates > 1 {
+                    println!(
+                        "{} of {} candidates agree",
+                        ballot.votes(&winner.output),
+                        ballot.len()
+                    );
+                }
+                return self
+                    .check(task, winner.output.clone(), Some(winner.code.clone()))
+                    .await;
+            }
+        }
+
+        // Without any output the error of the last candidate is returned, so it can escalate.
+        let Some(best) = ballot.best() else {
+            return Err(last_error.unwrap_or_else(|| anyhow!("no candidate printed an output")));
+        };
+        println!(
+            "No quorum of {}, submitting the candidate with the most confidence",
+            vote.quorum
+        );
+        self.check(task, best.output.clone(), Some(best.code.clone()))
+            .await
+    }
+
+    async fn check(
+        &self,
+        task: &TasksDto,
+        solution: String,
+        code: Option<String>,
+    ) -> anyhow::Result<bool> {
+        println!("Checking solution: {solution}");
+        let dto = CheckTaskRequestDto { solution, code };
+        let response = self.api().check_task(task.id, &dto).await?;
+        Ok(response.correct)
+    }
+
+    /// Asks the LLM for a program and runs it on the input and the examples. If it fails, the
+    /// errors are sent back to the LLM to fix the program, up to `max_repairs` times.
+    async fn write_code(
+        &self,
+        llm: &dyn LlmProvider,
+        description: &str,
+        part_two: bool,
+        input: Option<&str>,
+        examples: &[TaskExampleDto],
+    ) -> anyhow::Result<Candidate> {
+        let mut messages = prompt::code_task(description, part_two);
+        let mut previous: Option<Failure> = None;
+        let mut repair = 0;
+        loop {
+            if repair > 0 {
+                println!(
+                    "Asking for a repair ({repair}/{})",
+                    self.config.repair.max_repairs
+                );
+            }
+            let response = self.complete(llm, &messages).await?;
+            let result = match extract::solution_code(&response) {
+                Some(code) => self
+                    .run_code(code.clone(), input, examples)
+                    .await?
+                    .map(|run| Candidate {
+                        code,
+                        output: run.output,
+                        examples_passed: run.examples_passed,
+                    }),
+                None => Err(Failure::NoCode),
+            };
+
+            if let Some(failure) = &previous {
+                let kind = failure.kind();
+                let succeeded = result.is_ok();
+                println!(
+                    "Repair of {kind} {}",
+                    if succeeded { "succeeded" } else { "failed" }
+                );
+                self.repair_stats.lock().unwrap().record(&kind, succeeded);
+            }
+            let failure = match result {
+                Ok(candidate) => return Ok(candidate),
+                Err(failure) => failure,
+            };
+
+            println!("Failed: {}", failure.kind());
+            if repair == self.config.repair.max_repairs {
+                return Err(Unrepaired(failure).into());
+            }
+            messages.push(Message::assistant(response));
+            messages.push(Message::user(failure.feedback(&self.config.solver.limits)));
+            previous = Some(failure);
+            repair += 1;
+        }
+    }
+
+    async fn complete(
+        &self,
+        llm: &dyn LlmProvider,
+        messages: &[Message],
+    ) -> anyhow::Result<String> {
+        println!("Calling {}...", llm.model());
+        let completion = llm.complete(messages).await?;
+        if let (Some(prompt_tokens), Some(completion_tokens)) =
+            (completion.prompt_tokens, completion.completion_tokens)
+        {
+            println!("Tokens: {prompt_tokens} prompt, {completion_tokens} completion");
+        }
+        Ok(completion.content)
+    }
+
+    async fn run_code(
+        &self,
+        code: String,
+        input: Option<&str>,
+        examples: &[TaskExampleDto],
+    ) -> anyhow::Result<Result<Run, Failure>> {
+        let input = input.map(str::to_string);
+        let examples = examples.to_vec();
+        let solver = self.solver.clone();
+        let timeout = Duration::from_secs(self.config.solver.build_timeout_secs);
+        let limits = self.config.solver.limits.clone();
+
+        tokio::task::spawn_blocking(move || {
+            repair::run(
+                &solver,
+                &code,
+                input.as_deref(),
+                &examples,
+                timeout,
+                &limits,
+            )
+        })
+        .await?
+    }
+}
+
+/// The examples of the part that is solved.
+fn part_examples(examples: &[TaskExampleDto], part_two: bool) -> Vec<TaskExampleDto> {
+    let part = if part_two { 2 } else { 1 };
+    examples
+        .iter()
+        .filter(|example| example.part == part)
+        .cloned()
+        .collect()
+}
+
+async fn credentials(client: &Client, config: &AgentConfig) -> anyhow::Result<AgentCreatedDto> {
+    let path = &config.credentials_file;
+    if path.exists() {
+        let text = fs::read_to_string(path)?;
+        return serde_json::from_str(&text).with_context(|| format!("invalid {}", path.display()));
+    }
+
+    println!("Creating a new agent...");
+    let dto = AgentCreateDto {
+        name: config.name.clone(),
+    };
+    let credentials = client.create_agent(&dto).await?;
+    fs::write(path, serde_json::to_string_pretty(&credentials)?)?;
+    Ok(credentials)
+}
@@ -0,0 +1,325 @@
+use std::{env, fs, iter, path::PathBuf, time::Duration};
+
+use anyhow::{bail, Context};
+use sandbox::Limits;
+use serde::Deserialize;
+use solver::Solver;
+
+/// The contents of the config file, see `agent.example.toml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub backend: BackendConfig,
+    #[serde(default)]
+    pub agent: AgentConfig,
+    pub llm: LlmConfig,
+    #[serde(default)]
+    pub solver: SolverConfig,
+    #[serde(default)]
+    pub repair: RepairConfig,
+    #[serde(default)]
+    pub vote: VoteConfig,
+    #[serde(default)]
+    pub routing: RoutingConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackendConfig {
+    /// Base URL of the API, e.g. `http://32k.eu:8000/api`.
+    pub url: String,
+    pub user_token: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AgentConfig {
+    /// Name of the agent on the backend, used when it is created.
+    pub name: String,
+    /// Where the id and token of the agent are stored, so later runs continue as the same agent.
+    pub credentials_file: PathBuf,
+    /// How often a task is tried before moving on to the next one.
+    pub attempts_per_task: usize,
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            name: "agent-rs".into(),
+            credentials_file: "agent.json".into(),
+            attempts_per_task: 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "provider")]
+pub enum LlmConfig {
+    /// The Ollama chat API, `<base_url>/api/chat`.
+    #[serde(rename = "ollama")]
+    Ollama(ProviderConfig),
+    /// An OpenAI-compatible chat completions API, `<base_url>/chat/completions`.
+    #[serde(rename = "openai")]
+    OpenAi(ProviderConfig),
+}
+
+impl LlmConfig {
+    fn provider_mut(&mut self) -> &mut ProviderConfig {
+        match self {
+            LlmConfig::Ollama(config) | LlmConfig::OpenAi(config) => config,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderConfig {
+    pub base_url: String,
+    pub model: String,
+    /// Environment variable with the API key, sent as bearer token. Loading the config fails if
+    /// it is not set.
+    pub api_key_env: Option<String>,
+    /// The value of `api_key_env`, read by `Config::load`.
+    #[serde(skip)]
+    pub api_key: Option<String>,
+    pub temperature: Option<f64>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl ProviderConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    fn read_api_key(&mut self) -> anyhow::Result<()> {
+        if let Some(key) = &self.api_key_env {
+            let api_key = env::var(key).with_context(|| {
+                format!(
+                    "environment variable {key} of LLM {} is not set",
+                    self.model
+                )
+            })?;
+            self.api_key = Some(api_key);
+        }
+        Ok(())
+    }
+}
+
+fn default_timeout_secs() -> u64 {
+    600
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SolverConfig {
+    /// The `solver-rs` directory the attempts are created in.
+    pub dir: PathBuf,
+    pub build_timeout_secs: u64,
+    pub limits: Limits,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            dir: Solver::default_dir(),
+            build_timeout_secs: 120,
+            limits: Limits::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RepairConfig {
+    /// How often the LLM is asked to fix a program that does not build or run, per attempt.
+    pub max_repairs: usize,
+    /// Where the counts of the repairs per kind of failure are kept across runs.
+    pub stats_file: PathBuf,
+}
+
+impl Default for RepairConfig {
+    fn default() -> Self {
+        Self {
+            max_repairs: 3,
+            stats_file: "repair-stats.json".into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct VoteConfig {
+    /// How many programs are written per attempt at most. With 1 the output is submitted as is.
+    pub candidates: usize,
+    /// How many programs must print the same output before it is submitted. Without a quorum the
+    /// output of the program with the most confidence is submitted once all candidates ran.
+    pub quorum: usize,
+}
+
+impl VoteConfig {
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.candidates < 1 {
+            bail!("vote.candidates must be at least 1");
+        }
+        if self.quorum < 1 {
+            bail!("vote.quorum must be at least 1");
+        }
+        if self.quorum > self.candidates {
+            bail!(
+                "vote.quorum ({}) must not be more than vote.candidates ({})",
+                self.quorum,
+                self.candidates
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Default for VoteConfig {
+    fn default() -> Self {
+        Self {
+            candidates: 1,
+            quorum: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RoutingConfig {
+    /// Stronger (and more expensive) providers the attempts escalate to after `llm`, in order.
+    pub ladder: Vec<LlmConfig>,
+    /// How many attempts at a task fail on a tier before the next one is tried.
+    pub escalate_after: usize,
+    /// Kinds of failures that escalate right away if they could not be repaired, e.g.
+    /// `compile error E0499` or `compile error` for any compiler error, see `Failure::kind`.
+    pub escalate_on: Vec<String>,
+    /// Where the tier that solved each task is kept across runs.
+    pub stats_file: PathBuf,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            ladder: vec![],
+            escalate_after: 2,
+            escalate_on: vec![],
+            stats_file: "routing-stats.json".into(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("cannot read {path}"))?;
+        Self::parse(&text).with_context(|| format!("invalid config {path}"))
+    }
+
+    /// Parses and checks the config and reads the API keys of the providers from the environment.
+    fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut config: Self = toml::from_str(text)?;
+        config.vote.validate()?;
+        for llm in iter::once(&mut config.llm).chain(&mut config.routing.ladder) {
+            llm.provider_mut().read_api_key()?;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINIMAL: &str = r#"
+[backend]
+url = "http://localhost:8000/api"
+user_token = "token"
+
+[llm]
+provider = "ollama"
+base_url = "http://localhost:11434"
+model = "codestral"
+"#;
+
+    #[test]
+    fn parses_example() {
+        let config = Config::parse(include_str!("../agent.example.toml")).unwrap();
+
+        assert_eq!(config.backend.url, "http://32k.eu:8000/api");
+        assert_eq!(config.agent.attempts_per_task, 8);
+        let LlmConfig::Ollama(llm) = &config.llm else {
+            panic!("not Ollama: {:?}", config.llm);
+        };
+        assert_eq!(llm.model, "codestral:22b-v0.1-q6_K");
+        assert_eq!(llm.timeout(), Duration::from_secs(600));
+        assert_eq!(llm.api_key, None);
+        assert_eq!(config.solver.limits.wall_time, Duration::from_secs(20));
+        assert_eq!(config.routing.escalate_after, 2);
+        assert!(config.routing.ladder.is_empty());
+    }
+
+    #[test]
+    fn defaults_of_missing_sections() {
+        let config = Config::parse(MINIMAL).unwrap();
+
+        assert_eq!(config.agent.name, "agent-rs");
+        assert_eq!(config.repair.max_repairs, 3);
+        assert_eq!((config.vote.candidates, config.vote.quorum), (1, 1));
+        assert_eq!(config.solver.build_timeout_secs, 120);
+    }
+
+    #[test]
+    fn reads_api_keys() {
+        env::set_var("AGENT_RS_TEST_LLM_KEY", "secret");
+        env::set_var("AGENT_RS_TEST_LADDER_KEY", "other secret");
+        let config = Config::parse(&format!(
+            r#"{MINIMAL}api_key_env = "AGENT_RS_TEST_LLM_KEY"
+
+[[routing.ladder]]
+provider = "openai"
+base_url = "https://api.openai.com/v1"
+model = "gpt-4o"
+api_key_env = "AGENT_RS_TEST_LADDER_KEY"
+"#
+        ))
+        .unwrap();
+
+        let LlmConfig::Ollama(llm) = &config.llm else {
+            panic!("not Ollama: {:?}", config.llm);
+        };
+        assert_eq!(llm.api_key.as_deref(), Some("secret"));
+        let [LlmConfig::OpenAi(ladder)] = config.routing.ladder.as_slice() else {
+            panic!("not one OpenAI tier: {:?}", config.routing.ladder);
+        };
+        assert_eq!(ladder.api_key.as_deref(), Some("other secret"));
+    }
+
+    #[test]
+    fn missing_api_key_is_an_error() {
+        let error = Config::parse(&format!(
+            "{MINIMAL}api_key_env = \"AGENT_RS_TEST_MISSING_KEY\"\n"
+        ))
+        .unwrap_err();
+
+        assert!(error.to_string().contains("AGENT_RS_TEST_MISSING_KEY"));
+    }
+
+    #[test]
+    fn checks_vote() {
+        let vote = |candidates: usize, quorum: usize| {
+            Config::parse(&format!(
+                "{MINIMAL}\n[vote]\ncandidates = {candidates}\nquorum = {quorum}\n"
+            ))
+        };
+
+        assert!(vote(5, 3).is_ok());
+        assert!(vote(3, 3).is_ok());
+        for (candidates, quorum) in [(0, 0), (0, 1), (1, 0), (2, 3)] {
+            let error = vote(candidates, quorum).unwrap_err();
+            assert!(error.to_string().starts_with("vote."), "{error}");
+        }
+    }
+
+    #[test]
+    fn unknown_provider_is_an_error() {
+        assert!(Config::parse(&MINIMAL.replace("ollama", "llamafile")).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+//! An agent that solves the tasks of the backend with an LLM. The LLM provider, the backend and
+//! the solver are configured in a TOML file, see `agent.example.toml`.
+//!
+//! ```no_run
+//! # async fn run() -> anyhow::Result<()> {
+//! let config = agent::Config::load("agent.toml")?;
+//! agent::Agent::new(config).await?.run().await
+//! # }
+//! ```
+
+mod agent;
+mod config;
+mod llm;
+mod ollama;
+mod openai;
+mod prompt;
+mod repair;
+mod routing;
+mod vote;
+
+pub use agent::Agent;
+pub use config::{
+    AgentConfig, BackendConfig, Config, LlmConfig, ProviderConfig, RepairConfig, SolverConfig,
+};
+pub use llm::{provider, Completion, LlmProvider, Message, Role};
+pub use ollama::Ollama;
+pub use openai::OpenAi;
+pub use repair::{Failure, RepairCount, RepairStats, Run, Unrepaired};
+pub use routing::{RoutingStats, TaskRoute};
+pub use vote::{Ballot, Candidate};
@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::LlmConfig, ollama::Ollama, openai::OpenAi};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+/// One message of a chat, in the format both the Ollama and the OpenAI API use.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: String,
+}
+
+impl Message {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::System,
+            content: content.into(),
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: content.into(),
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: content.into(),
+        }
+    }
+}
+
+/// The answer of the model, with the token counts if the provider reports them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Completion {
+    pub content: String,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+}
+
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Name of the model, for the log.
+    fn model(&self) -> &str;
+
+    /// Continues the chat with the next message of the assistant.
+    async fn complete(&self, messages: &[Message]) -> anyhow::Result<Completion>;
+}
+
+/// The provider selected in the config file.
+pub fn provider(config: &LlmConfig) -> anyhow::Result<Box<dyn LlmProvider>> {
+    Ok(match config {
+        LlmConfig::Ollama(config) => Box::new(Ollama::new(config)?),
+        LlmConfig::OpenAi(config) => Box::new(OpenAi::new(config)?),
+    })
+}
@@ -0,0 +1,14 @@
+use std::env;
+
+use agent::{Agent, Config};
+
+const DEFAULT_CONFIG: &str = "agent.toml";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let path = env::args().nth(1).unwrap_or(DEFAULT_CONFIG.into());
+    let config = Config::load(&path)?;
+
+    println!("Starting the agent...");
+    Agent::new(config).await?.run().await
+}
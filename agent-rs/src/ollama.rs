@@ -0,0 +1,120 @@
+use anyhow::bail;
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    config::ProviderConfig,
+    llm::{Completion, LlmProvider, Message},
+};
+
+/// The chat API of Ollama, without streaming.
+pub struct Ollama {
+    http: reqwest::Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    temperature: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: Message,
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
+}
+
+impl Ollama {
+    pub fn new(config: &ProviderConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(config.timeout())
+                .build()?,
+            url: format!("{}/api/chat", config.base_url.trim_end_matches('/')),
+            model: config.model.clone(),
+            api_key: config.api_key.clone(),
+            temperature: config.temperature,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for Ollama {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(&self, messages: &[Message]) -> anyhow::Result<Completion> {
+        let mut request = json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": false,
+        });
+        if let Some(temperature) = self.temperature {
+            request["options"] = json!({ "temperature": temperature });
+        }
+
+        let mut builder = self.http.post(&self.url).json(&request);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        let response = builder.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!("Ollama returned {status}: {}", response.text().await?);
+        }
+
+        let response: ChatResponse = response.json().await?;
+        Ok(response.into())
+    }
+}
+
+impl From<ChatResponse> for Completion {
+    fn from(response: ChatResponse) -> Self {
+        Self {
+            content: response.message.content,
+            prompt_tokens: response.prompt_eval_count,
+            completion_tokens: response.eval_count,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::Role;
+
+    #[test]
+    fn parses_response() {
+        let response: ChatResponse = serde_json::from_str(
+            r#"{"model":"codestral:22b-v0.1-q6_K","created_at":"2024-06-01T10:00:00.000Z",
+            "message":{"role":"assistant","content":"fn main() {}"},"done_reason":"stop",
+            "done":true,"total_duration":5191566416,"load_duration":2154458,
+            "prompt_eval_count":26,"prompt_eval_duration":383809000,"eval_count":298,
+            "eval_duration":4799921000}"#,
+        )
+        .unwrap();
+        assert_eq!(response.message.role, Role::Assistant);
+        assert_eq!(
+            Completion::from(response),
+            Completion {
+                content: "fn main() {}".into(),
+                prompt_tokens: Some(26),
+                completion_tokens: Some(298),
+            }
+        );
+    }
+
+    #[test]
+    fn token_counts_are_optional() {
+        // Left out if the prompt was cached.
+        let response: ChatResponse = serde_json::from_str(
+            r#"{"model":"codestral","message":{"role":"assistant","content":"42"},"done":true}"#,
+        )
+        .unwrap();
+        let completion = Completion::from(response);
+        assert_eq!(completion.content, "42");
+        assert_eq!(completion.prompt_tokens, None);
+        assert_eq!(completion.completion_tokens, None);
+    }
+}
@@ -0,0 +1,146 @@
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    config::ProviderConfig,
+    llm::{Completion, LlmProvider, Message},
+};
+
+/// An OpenAI-compatible chat completions API (OpenAI, vLLM, llama.cpp, LM Studio, ...).
+pub struct OpenAi {
+    http: reqwest::Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    temperature: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<Choice>,
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize)]
+struct Choice {
+    message: Message,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    prompt_tokens: Option<u64>,
+    completion_tokens: Option<u64>,
+}
+
+impl OpenAi {
+    pub fn new(config: &ProviderConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(config.timeout())
+                .build()?,
+            url: format!("{}/chat/completions", config.base_url.trim_end_matches('/')),
+            model: config.model.clone(),
+            api_key: config.api_key.clone(),
+            temperature: config.temperature,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAi {
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn complete(&self, messages: &[Message]) -> anyhow::Result<Completion> {
+        let mut request = json!({
+            "model": self.model,
+            "messages": messages,
+        });
+        if let Some(temperature) = self.temperature {
+            request["temperature"] = json!(temperature);
+        }
+
+        let mut builder = self.http.post(&self.url).json(&request);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        let response = builder.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!(
+                "chat completions returned {status}: {}",
+                response.text().await?
+            );
+        }
+
+        let response: ChatResponse = response.json().await?;
+        response.try_into()
+    }
+}
+
+impl TryFrom<ChatResponse> for Completion {
+    type Error = anyhow::Error;
+
+    fn try_from(response: ChatResponse) -> anyhow::Result<Self> {
+        let choice = response
+            .choices
+            .into_iter()
+            .next()
+            .context("chat completions returned no choices")?;
+        let usage = response.usage;
+        Ok(Self {
+            content: choice.message.content,
+            prompt_tokens: usage.as_ref().and_then(|usage| usage.prompt_tokens),
+            completion_tokens: usage.as_ref().and_then(|usage| usage.completion_tokens),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completion(json: &str) -> anyhow::Result<Completion> {
+        serde_json::from_str::<ChatResponse>(json)?.try_into()
+    }
+
+    #[test]
+    fn parses_response() {
+        let completion = completion(
+            r#"{"id":"chatcmpl-123","object":"chat.completion","created":1677652288,
+            "model":"gpt-4o","system_fingerprint":"fp_44709d6fcb",
+            "choices":[{"index":0,"message":{"role":"assistant","content":"fn main() {}"},
+            "logprobs":null,"finish_reason":"stop"}],
+            "usage":{"prompt_tokens":9,"completion_tokens":12,"total_tokens":21}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            completion,
+            Completion {
+                content: "fn main() {}".into(),
+                prompt_tokens: Some(9),
+                completion_tokens: Some(12),
+            }
+        );
+    }
+
+    #[test]
+    fn usage_is_optional() {
+        // Some compatible servers (e.g. older llama.cpp versions) do not report it.
+        let completion = completion(
+            r#"{"choices":[{"index":0,"message":{"role":"assistant","content":"42"}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(completion.content, "42");
+        assert_eq!(completion.prompt_tokens, None);
+    }
+
+    #[test]
+    fn no_choices_is_an_error() {
+        let error = completion(r#"{"choices":[],"usage":null}"#).unwrap_err();
+        assert!(error.to_string().contains("no choices"));
+    }
+}
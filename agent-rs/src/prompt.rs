@@ -0,0 +1,35 @@
+use solver::TEMPLATE;
+
+use crate::llm::Message;
+
+const SIMPLE_TASK: &str = "You are a puzzle/task solver bot. You are given a task to solve. Think \
+out step-by-step and put the final solution in backticks at the end of your response. For \
+example, if the solution is 42, you should return `42`.";
+
+const ADVENT_OF_CODE: &str = "You are an expert \"Advent of code\" challenge solver. You are \
+given a coding puzzle and you generate correct (compilable), efficient and idiomatic Rust code \
+which solves the puzzle for a given input. The code reads the input from `input.txt` and prints \
+only the solution. Think out step-by-step first, then output the Rust code in one ```rust block.";
+
+/// The chat to solve a task by answering directly.
+pub fn simple_task(description: &str) -> Vec<Message> {
+    vec![
+        Message::system(SIMPLE_TASK),
+        Message::user(format!("The task is: {description}")),
+    ]
+}
+
+/// The chat to solve a task by writing a Rust program for the input.
+pub fn code_task(description: &str, part_two: bool) -> Vec<Message> {
+    let part = if part_two {
+        "You must only solve the task from \"Part Two\" of the challenge.\n\n"
+    } else {
+        ""
+    };
+    vec![
+        Message::system(ADVENT_OF_CODE),
+        Message::user(format!(
+            "{part}Use this template:\n\n```rust\n{TEMPLATE}```\n\nThe challenge is:\n{description}"
+        )),
+    ]
+}
@@ -0,0 +1,360 @@
+use std::{collections::BTreeMap, error::Error, fmt, fs, path::Path, time::Duration};
+
+use client::TaskExampleDto;
+use sandbox::{Build, Execution, Limits};
+use serde::{Deserialize, Serialize};
+use solver::Solver;
+
+/// Compiler errors beyond this are left out of the feedback.
+const MAX_ERRORS: usize = 10;
+/// Only the end of the stderr of a crash is fed back, where the panic message is.
+const MAX_STDERR_LEN: usize = 2000;
+
+const FIX: &str = "Fix the program and output the complete corrected program in one ```rust block.";
+
+/// Why a generated program did not print an output.
+#[derive(Debug, Clone)]
+pub enum Failure {
+    NoCode,
+    CompileError(Build),
+    CompileTimeout,
+    Crash(Execution),
+    Timeout,
+    NoOutput,
+}
+
+impl Failure {
+    /// The key of the failure in the statistics, e.g. `compile error E0308` or `panic`.
+    pub fn kind(&self) -> String {
+        match self {
+            Failure::NoCode => "no code".into(),
+            Failure::CompileError(build) => {
+                match build.errors().find_map(|error| error.code.clone()) {
+                    Some(code) => format!("compile error {code}"),
+                    None => "compile error".into(),
+                }
+            }
+            Failure::CompileTimeout => "compile timeout".into(),
+            Failure::Crash(execution) if execution.stderr.contains("panicked at") => "panic".into(),
+            Failure::Crash(_) => "crash".into(),
+            Failure::Timeout => "timeout".into(),
+            Failure::NoOutput => "no output".into(),
+        }
+    }
+
+    /// The follow-up message that asks the LLM to repair the program.
+    pub fn feedback(&self, limits: &Limits) -> String {
+        match self {
+            Failure::NoCode => {
+                "Your response contains no Rust program with a `fn main`. Output the complete \
+                 program in one ```rust block."
+                    .into()
+            }
+            Failure::CompileError(build) => {
+                let mut errors: Vec<String> = build
+                    .errors()
+                    .take(MAX_ERRORS)
+                    .map(|error| error.to_string())
+                    .collect();
+                if errors.is_empty() {
+                    errors.push(if build.rendered.is_empty() {
+                        build.stderr.clone()
+                    } else {
+                        build.rendered.clone()
+                    });
+                }
+                format!(
+                    "The program does not compile:\n\n{}\n\n{FIX}",
+                    errors.join("\n")
+                )
+            }
+            Failure::CompileTimeout => {
+                format!(
+                    "Compiling the program took too long, e.g. because of huge constants. {FIX}"
+                )
+            }
+            Failure::Crash(execution) => {
+                let stderr = execution.stderr.trim();
+                let start = stderr
+                    .char_indices()
+                    .map(|(index, _)| index)
+                    .find(|index| stderr.len() - index <= MAX_STDERR_LEN)
+                    .unwrap_or(stderr.len());
+                let status = match (execution.exit_code, execution.signal) {
+                    (Some(exit_code), _) => format!("exit code {exit_code}"),
+                    (None, Some(signal)) => format!("signal {signal}"),
+                    (None, None) => "an unknown status".into(),
+                };
+                format!(
+                    "The program crashed with {status}. Its stderr was:\n\n{}\n\n{FIX}",
+                    &stderr[start..]
+                )
+            }
+            Failure::Timeout => format!(
+                "The program did not finish within {} s of CPU time ({} s wall time). Use a more \
+                 efficient algorithm. {FIX}",
+                limits.cpu_time.as_secs(),
+                limits.wall_time.as_secs()
+            ),
+            Failure::NoOutput => {
+                format!("The program printed nothing, it must print the solution. {FIX}")
+            }
+        }
+    }
+}
+
+/// The error of an attempt whose program still failed after all repairs.
+#[derive(Debug)]
+pub struct Unrepaired(pub Failure);
+
+impl fmt::Display for Unrepaired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no working code after all repairs ({})", self.0.kind())
+    }
+}
+
+impl Error for Unrepaired {}
+
+/// The output of a program that ran.
+#[derive(Debug, Clone)]
+pub struct Run {
+    /// The trimmed output for the input, empty if there is no input.
+    pub output: String,
+    /// How many of the examples the program printed the expected output for.
+    pub examples_passed: usize,
+}
+
+/// Builds the code in an attempt of its own and runs it on the input, then on the examples. Without
+/// input the code is only run on the examples.
+pub fn run(
+    solver: &Solver,
+    code: &str,
+    input: Option<&str>,
+    examples: &[TaskExampleDto],
+    build_timeout: Duration,
+    limits: &Limits,
+) -> anyhow::Result<Result<Run, Failure>> {
+    let attempt = solver.create()?;
+    let result = (|| {
+        attempt.write_source(code)?;
+
+        println!("Trying to build the generated code...");
+        let build = attempt.build(build_timeout)?;
+        if build.timed_out {
+            return Ok(Err(Failure::CompileTimeout));
+        }
+        if !build.success {
+            return Ok(Err(Failure::CompileError(build)));
+        }
+
+        let mut output = String::new();
+        if let Some(input) = input {
+            println!("Trying to run the generated code...");
+            attempt.write_input(input)?;
+            let execution = attempt.run(limits)?;
+            if execution.timed_out {
+                return Ok(Err(Failure::Timeout));
+            }
+            if !execution.success() {
+                return Ok(Err(Failure::Crash(execution)));
+            }
+            output = execution.stdout.trim().to_string();
+            if output.is_empty() {
+                return Ok(Err(Failure::NoOutput));
+            }
+        }
+
+        let mut examples_passed = 0;
+        for example in examples {
+            attempt.write_input(&example.input)?;
+            let execution = attempt.run(limits)?;
+            if execution.success() && execution.stdout.trim() == example.expected_output.trim() {
+                examples_passed += 1;
+            }
+        }
+        Ok(Ok(Run {
+            output,
+            examples_passed,
+        }))
+    })();
+    // A leftover attempt only takes up disk space, it must not hide the result.
+    let id = attempt.id.clone();
+    if let Err(error) = attempt.remove() {
+        println!("Cannot remove attempt {id}: {error}");
+    }
+    result
+}
+
+/// How often a repair was asked for per kind of failure, and how often the repaired program ran.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepairStats {
+    pub kinds: BTreeMap<String, RepairCount>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RepairCount {
+    pub attempted: u64,
+    pub succeeded: u64,
+}
+
+impl RepairStats {
+    /// The statistics of earlier runs, or empty ones if there is no file yet.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, kind: &str, succeeded: bool) {
+        let count = self.kinds.entry(kind.to_string()).or_default();
+        count.attempted += 1;
+        if succeeded {
+            count.succeeded += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sandbox::Diagnostic;
+
+    use super::*;
+
+    fn build(diagnostics: Vec<Diagnostic>, rendered: &str, stderr: &str) -> Build {
+        Build {
+            success: false,
+            timed_out: false,
+            executable: None,
+            diagnostics,
+            rendered: rendered.into(),
+            stderr: stderr.into(),
+        }
+    }
+
+    fn diagnostic(level: &str, code: Option<&str>, message: &str) -> Diagnostic {
+        Diagnostic {
+            level: level.into(),
+            code: code.map(Into::into),
+            message: message.into(),
+            file: Some("src/main.rs".into()),
+            line: Some(3),
+            column: Some(5),
+            label: None,
+            notes: vec![],
+            suggestions: vec![],
+        }
+    }
+
+    fn execution(exit_code: Option<i32>, signal: Option<i32>, stderr: &str) -> Execution {
+        Execution {
+            exit_code,
+            signal,
+            timed_out: false,
+            stdout: String::new(),
+            stderr: stderr.into(),
+            output_truncated: false,
+            wall_time_ms: 1,
+            cpu_time_ms: 1,
+        }
+    }
+
+    #[test]
+    fn kind_of_compile_error_is_the_first_error_code() {
+        let failure = Failure::CompileError(build(
+            vec![
+                diagnostic("warning", Some("unused_variables"), "unused variable"),
+                diagnostic("error", None, "expected item"),
+                diagnostic("error", Some("E0308"), "mismatched types"),
+                diagnostic("error", Some("E0425"), "cannot find value"),
+            ],
+            "",
+            "",
+        ));
+        assert_eq!(failure.kind(), "compile error E0308");
+
+        let failure = Failure::CompileError(build(vec![], "", "error: no manifest"));
+        assert_eq!(failure.kind(), "compile error");
+    }
+
+    #[test]
+    fn kind_of_crash_tells_panics_apart() {
+        let panic = execution(
+            Some(101),
+            None,
+            "thread 'main' panicked at src/main.rs:4:5:\nindex out of bounds",
+        );
+        assert_eq!(Failure::Crash(panic).kind(), "panic");
+        assert_eq!(
+            Failure::Crash(execution(None, Some(11), "")).kind(),
+            "crash"
+        );
+        assert_eq!(Failure::NoCode.kind(), "no code");
+        assert_eq!(Failure::CompileTimeout.kind(), "compile timeout");
+        assert_eq!(Failure::Timeout.kind(), "timeout");
+        assert_eq!(Failure::NoOutput.kind(), "no output");
+    }
+
+    #[test]
+    fn compile_error_feedback_lists_only_errors() {
+        let mut diagnostics = vec![diagnostic("warning", None, "unused variable")];
+        for index in 0..MAX_ERRORS + 2 {
+            diagnostics.push(diagnostic(
+                "error",
+                Some("E0308"),
+                &format!("error {index}"),
+            ));
+        }
+        let feedback =
+            Failure::CompileError(build(diagnostics, "", "")).feedback(&Limits::default());
+
+        assert!(feedback.starts_with("The program does not compile:\n\n"));
+        assert!(feedback.ends_with(FIX));
+        assert!(!feedback.contains("unused variable"));
+        assert_eq!(feedback.matches("error[E0308]").count(), MAX_ERRORS);
+        assert!(feedback.contains("src/main.rs:3:5: error[E0308]: error 0"));
+    }
+
+    #[test]
+    fn compile_error_feedback_without_diagnostics() {
+        let rendered = Failure::CompileError(build(vec![], "rendered output", "cargo output"));
+        assert!(rendered
+            .feedback(&Limits::default())
+            .contains("rendered output"));
+
+        let stderr = Failure::CompileError(build(vec![], "", "cargo output"));
+        assert!(stderr.feedback(&Limits::default()).contains("cargo output"));
+    }
+
+    #[test]
+    fn crash_feedback_keeps_the_end_of_stderr() {
+        let stderr = format!("{}thread 'main' panicked", "ä".repeat(MAX_STDERR_LEN));
+        let feedback =
+            Failure::Crash(execution(Some(101), None, &stderr)).feedback(&Limits::default());
+
+        assert!(feedback.starts_with("The program crashed with exit code 101."));
+        assert!(feedback.contains("thread 'main' panicked\n\n"));
+        let kept = feedback.matches('ä').count();
+        assert!(kept > 0 && kept * 'ä'.len_utf8() <= MAX_STDERR_LEN);
+
+        let feedback = Failure::Crash(execution(None, Some(11), "")).feedback(&Limits::default());
+        assert!(feedback.starts_with("The program crashed with signal 11."));
+    }
+
+    #[test]
+    fn timeout_feedback_states_the_limits() {
+        let limits = Limits {
+            cpu_time: Duration::from_secs(3),
+            wall_time: Duration::from_secs(7),
+            ..Limits::default()
+        };
+        assert!(Failure::Timeout
+            .feedback(&limits)
+            .starts_with("The program did not finish within 3 s of CPU time (7 s wall time)."));
+    }
+}
@@ -0,0 +1,212 @@
+use std::{collections::BTreeMap, fs, future::Future, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config::RoutingConfig, repair::Unrepaired};
+
+/// Tries a task up to `attempts` times with `solve`, which gets the tier of the attempt and tells
+/// whether the solution was correct. It starts on the first of the `models` and escalates to the
+/// next one when `escalate` says so, until a tier solves the task.
+pub async fn climb<F, Fut>(
+    config: &RoutingConfig,
+    name: &str,
+    models: &[&str],
+    attempts: usize,
+    mut solve: F,
+) -> TaskRoute
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = anyhow::Result<bool>>,
+{
+    let mut route = TaskRoute {
+        name: name.to_string(),
+        attempts: vec![0; models.len()],
+        ..TaskRoute::default()
+    };
+    let mut tier = 0;
+    let mut failed_attempts = 0;
+    for attempt in 1..=attempts {
+        println!("Trying to solve task: {name} (attempt {attempt})");
+        route.attempts[tier] += 1;
+        let error = match solve(tier).await {
+            Ok(true) => {
+                println!("Solution was correct");
+                route.solved_tier = Some(tier);
+                route.solved_model = Some(models[tier].to_string());
+                break;
+            }
+            Ok(false) => {
+                println!("Solution was not correct");
+                None
+            }
+            Err(error) => {
+                println!("Error: {error:#}");
+                Some(error)
+            }
+        };
+
+        failed_attempts += 1;
+        if tier + 1 < models.len() && escalate(config, failed_attempts, error.as_ref()) {
+            tier += 1;
+            failed_attempts = 0;
+            println!("Escalating to {}", models[tier]);
+        }
+    }
+    route
+}
+
+/// Whether the next attempt at a task goes to the next tier: after `escalate_after` failed
+/// attempts on the current one, or right away if the program failed in a way listed in
+/// `escalate_on`, e.g. `compile error E0499` or `timeout`.
+pub fn escalate(
+    config: &RoutingConfig,
+    failed_attempts: usize,
+    error: Option<&anyhow::Error>,
+) -> bool {
+    if failed_attempts >= config.escalate_after {
+        return true;
+    }
+    let Some(Unrepaired(failure)) = error.and_then(|error| error.downcast_ref()) else {
+        return false;
+    };
+    let kind = failure.kind();
+    config
+        .escalate_on
+        .iter()
+        .any(|pattern| kind.starts_with(pattern.as_str()))
+}
+
+/// Which tier solved which task, by task id, to tune the ladder.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoutingStats {
+    pub tasks: BTreeMap<String, TaskRoute>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskRoute {
+    pub name: String,
+    /// The attempts per tier, in the order of the ladder.
+    pub attempts: Vec<usize>,
+    /// The tier and model that solved the task.
+    pub solved_tier: Option<usize>,
+    pub solved_model: Option<String>,
+}
+
+impl RoutingStats {
+    /// The statistics of earlier runs, or empty ones if there is no file yet.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// How many tasks each tier solved.
+    pub fn solved_per_tier(&self) -> BTreeMap<usize, usize> {
+        let mut solved = BTreeMap::new();
+        for tier in self.tasks.values().filter_map(|route| route.solved_tier) {
+            *solved.entry(tier).or_default() += 1;
+        }
+        solved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::repair::Failure;
+
+    const MODELS: [&str; 3] = ["small", "medium", "large"];
+
+    fn config(escalate_after: usize, escalate_on: &[&str]) -> RoutingConfig {
+        RoutingConfig {
+            escalate_after,
+            escalate_on: escalate_on.iter().map(|kind| kind.to_string()).collect(),
+            ..RoutingConfig::default()
+        }
+    }
+
+    fn unrepaired(failure: Failure) -> anyhow::Error {
+        Unrepaired(failure).into()
+    }
+
+    /// Climbs with the results in order, returns the route and the tier of every attempt.
+    async fn climb_with(
+        config: &RoutingConfig,
+        attempts: usize,
+        results: Vec<anyhow::Result<bool>>,
+    ) -> (TaskRoute, Vec<usize>) {
+        let results = RefCell::new(results.into_iter());
+        let tiers = RefCell::new(vec![]);
+        let route = climb(config, "task", &MODELS, attempts, |tier| {
+            tiers.borrow_mut().push(tier);
+            let result = results.borrow_mut().next().expect("no attempt left");
+            async move { result }
+        })
+        .await;
+        (route, tiers.into_inner())
+    }
+
+    #[test]
+    fn escalates_after_failed_attempts() {
+        let config = config(2, &[]);
+        assert!(!escalate(&config, 1, None));
+        assert!(escalate(&config, 2, None));
+        assert!(!escalate(&config, 1, Some(&anyhow!("backend down"))));
+    }
+
+    #[test]
+    fn escalates_right_away_on_listed_failures() {
+        let config = config(5, &["compile error", "timeout"]);
+        assert!(escalate(&config, 1, Some(&unrepaired(Failure::Timeout))));
+        assert!(!escalate(&config, 1, Some(&unrepaired(Failure::NoOutput))));
+        assert!(!escalate(
+            &config,
+            1,
+            Some(&unrepaired(Failure::CompileTimeout))
+        ));
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_first_tier_that_solves() {
+        let config = config(1, &[]);
+        let (route, tiers) = climb_with(&config, 5, vec![Ok(false), Ok(true)]).await;
+        assert_eq!(tiers, [0, 1]);
+        assert_eq!(route.attempts, [1, 1, 0]);
+    }
+
+    #[tokio::test]
+    async fn records_the_solving_tier() {
+        let config = config(2, &["timeout"]);
+        let results = vec![
+            Ok(false),
+            Err(unrepaired(Failure::Timeout)),
+            Ok(false),
+            Ok(false),
+            Ok(true),
+        ];
+        let (route, tiers) = climb_with(&config, 5, results).await;
+        assert_eq!(tiers, [0, 0, 1, 1, 2]);
+        assert_eq!(route.solved_tier, Some(2));
+        assert_eq!(route.solved_model.as_deref(), Some("large"));
+    }
+
+    #[tokio::test]
+    async fn stays_on_the_last_tier() {
+        let config = config(1, &[]);
+        let (route, tiers) = climb_with(&config, 4, (0..4).map(|_| Ok(false)).collect()).await;
+        assert_eq!(tiers, [0, 1, 2, 2]);
+        assert_eq!(route.attempts, [1, 1, 2]);
+        assert_eq!(route.solved_tier, None);
+        assert_eq!(route.solved_model, None);
+    }
+}
@@ -0,0 +1,148 @@
+/// A program that ran on the input, with what it printed.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub code: String,
+    pub output: String,
+    pub examples_passed: usize,
+}
+
+/// The candidates of one attempt at a task. Candidates that fail an example do not vote, as
+/// their output is likely wrong too.
+#[derive(Debug, Clone, Default)]
+pub struct Ballot {
+    candidates: Vec<Candidate>,
+    examples: usize,
+}
+
+impl Ballot {
+    pub fn new(examples: usize) -> Self {
+        Self {
+            candidates: vec![],
+            examples,
+        }
+    }
+
+    pub fn push(&mut self, candidate: Candidate) {
+        self.candidates.push(candidate);
+    }
+
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// How many of the voting candidates printed the output.
+    pub fn votes(&self, output: &str) -> usize {
+        self.candidates
+            .iter()
+            .filter(|candidate| candidate.examples_passed == self.examples)
+            .filter(|candidate| candidate.output == output)
+            .count()
+    }
+
+    /// The first candidate whose output at least `quorum` candidates agree on.
+    pub fn winner(&self, quorum: usize) -> Option<&Candidate> {
+        self.candidates
+            .iter()
+            .filter(|candidate| candidate.examples_passed == self.examples)
+            .find(|candidate| self.votes(&candidate.output) >= quorum)
+    }
+
+    /// The candidate with the most confidence if there is no quorum: the one that passed the most
+    /// examples, then the one with the most votes, then the first one.
+    pub fn best(&self) -> Option<&Candidate> {
+        self.candidates
+            .iter()
+            .rev()
+            .max_by_key(|candidate| (candidate.examples_passed, self.votes(&candidate.output)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(code: &str, output: &str, examples_passed: usize) -> Candidate {
+        Candidate {
+            code: code.into(),
+            output: output.into(),
+            examples_passed,
+        }
+    }
+
+    fn ballot(examples: usize, candidates: &[Candidate]) -> Ballot {
+        let mut ballot = Ballot::new(examples);
+        for candidate in candidates {
+            ballot.push(candidate.clone());
+        }
+        ballot
+    }
+
+    #[test]
+    fn tie_goes_to_the_first_output() {
+        let ballot = ballot(
+            1,
+            &[
+                candidate("a", "1", 1),
+                candidate("b", "2", 1),
+                candidate("c", "2", 1),
+                candidate("d", "1", 1),
+            ],
+        );
+        assert_eq!(ballot.votes("1"), 2);
+        assert_eq!(ballot.votes("2"), 2);
+        assert_eq!(ballot.winner(2).unwrap().code, "a");
+        assert_eq!(ballot.best().unwrap().code, "a");
+    }
+
+    #[test]
+    fn no_quorum_falls_back_to_the_best_candidate() {
+        let ballot = ballot(
+            2,
+            &[
+                candidate("a", "1", 1),
+                candidate("b", "2", 2),
+                candidate("c", "3", 0),
+            ],
+        );
+        assert!(ballot.winner(2).is_none());
+        assert_eq!(ballot.best().unwrap().code, "b");
+    }
+
+    #[test]
+    fn early_winner_while_candidates_are_pending() {
+        // Of five planned candidates, two of the first three agree: the rest is not needed.
+        let mut ballot = ballot(1, &[candidate("a", "7", 1), candidate("b", "8", 1)]);
+        assert!(ballot.winner(2).is_none());
+        ballot.push(candidate("c", "8", 1));
+        assert_eq!(ballot.len(), 3);
+        assert_eq!(ballot.winner(2).unwrap().code, "b");
+    }
+
+    #[test]
+    fn candidates_failing_an_example_do_not_vote() {
+        let ballot = ballot(
+            1,
+            &[
+                candidate("a", "5", 0),
+                candidate("b", "5", 0),
+                candidate("c", "6", 1),
+            ],
+        );
+        assert_eq!(ballot.votes("5"), 0);
+        assert!(ballot.winner(2).is_none());
+        assert_eq!(ballot.winner(1).unwrap().code, "c");
+        assert_eq!(ballot.best().unwrap().code, "c");
+    }
+
+    #[test]
+    fn empty_ballot_has_no_winner() {
+        let ballot = Ballot::new(0);
+        assert!(ballot.is_empty());
+        assert!(ballot.winner(1).is_none());
+        assert!(ballot.best().is_none());
+    }
+}
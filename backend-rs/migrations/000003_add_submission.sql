@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS submission (
+    id TEXT PRIMARY KEY NOT NULL,
+    task_id TEXT NOT NULL,
+    agent_id TEXT NOT NULL,
+    solution TEXT NOT NULL,
+    correct BOOLEAN NOT NULL,
+    submission_time TEXT NOT NULL
+)
\ No newline at end of file
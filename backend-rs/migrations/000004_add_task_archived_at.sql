@@ -0,0 +1 @@
+ALTER TABLE task ADD COLUMN archived_at TEXT
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE task ADD COLUMN aoc_year INTEGER;
+ALTER TABLE task ADD COLUMN aoc_day INTEGER;
+ALTER TABLE task ADD COLUMN aoc_part INTEGER;
+CREATE UNIQUE INDEX IF NOT EXISTS task_aoc_puzzle ON task (aoc_year, aoc_day, aoc_part)
\ No newline at end of file
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS task_input (
+    task_id TEXT PRIMARY KEY NOT NULL,
+    compressed BOOLEAN NOT NULL,
+    data BLOB NOT NULL
+)
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE submission ADD COLUMN code TEXT
\ No newline at end of file
@@ -0,0 +1 @@
+ALTER TABLE submission ADD COLUMN runtime TEXT
\ No newline at end of file
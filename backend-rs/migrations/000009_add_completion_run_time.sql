@@ -0,0 +1 @@
+ALTER TABLE completion ADD COLUMN best_run_time_in_ms INTEGER
\ No newline at end of file
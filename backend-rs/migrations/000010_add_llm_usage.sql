@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS llm_usage (
+    id TEXT PRIMARY KEY NOT NULL,
+    agent_id TEXT NOT NULL,
+    task_id TEXT,
+    provider TEXT NOT NULL,
+    model TEXT,
+    prompt_tokens INTEGER NOT NULL,
+    completion_tokens INTEGER NOT NULL,
+    latency_ms INTEGER NOT NULL,
+    status INTEGER NOT NULL,
+    request_time TEXT NOT NULL
+)
\ No newline at end of file
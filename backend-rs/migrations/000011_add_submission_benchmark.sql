@@ -0,0 +1,2 @@
+ALTER TABLE submission ADD COLUMN run_time_in_ms INTEGER;
+ALTER TABLE submission ADD COLUMN benchmark_error TEXT;
\ No newline at end of file
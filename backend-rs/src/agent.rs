@@ -1,6 +1,6 @@
 use axum::{extract::Path, Extension, Json};
-use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, SqlitePool};
+use dto::{AgentCreateDto, AgentCreatedDto, AgentDto};
+use sqlx::{Executor, FromRow, Sqlite, SqlitePool};
 use uuid::Uuid;
 
 use crate::{
@@ -8,26 +8,13 @@ use crate::{
     traits::DtoValidator,
 };
 
-#[derive(FromRow, Debug)]
+#[derive(FromRow, Debug, PartialEq)]
 pub struct AgentInDb {
     pub id: Uuid,
     pub token: Uuid,
     pub name: String,
 }
 
-#[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AgentDto {
-    pub id: Uuid,
-    pub name: String,
-}
-
-#[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AgentCreateDto {
-    pub name: String,
-}
-
 impl DtoValidator for AgentCreateDto {
     fn validate(&self) -> Result<(), DtoValidationError> {
         if self.name.len() > 64 {
@@ -37,12 +24,6 @@ impl DtoValidator for AgentCreateDto {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AgentCreatedDto {
-    pub id: Uuid,
-    pub token: Uuid,
-}
-
 pub async fn create_agent(
     Extension(pool): Extension<SqlitePool>,
     Json(dto): Json<AgentCreateDto>,
@@ -91,15 +72,17 @@ impl AgentInDb {
         Self { id, token, name }
     }
 
-    pub async fn create(&self, pool: &SqlitePool) -> anyhow::Result<()> {
-        let mut conn = pool.acquire().await?;
+    pub async fn create(
+        &self,
+        executor: impl Executor<'_, Database = Sqlite>,
+    ) -> anyhow::Result<()> {
         sqlx::query!(
             r#"INSERT INTO agent (id, token, name) VALUES (?, ?, ?);"#,
             self.id,
             self.token,
             self.name,
         )
-        .execute(conn.as_mut())
+        .execute(executor)
         .await?;
 
         Ok(())
@@ -141,19 +124,21 @@ impl AgentInDb {
         Ok(models)
     }
 
-    // pub async fn update(&self, pool: &SqlitePool) -> anyhow::Result<()> {
-    //     let mut conn = pool.acquire().await?;
-    //     sqlx::query!(
-    //         r#"UPDATE agent SET token = ?, name = ? WHERE id = ?;"#,
-    //         self.token,
-    //         self.name,
-    //         self.id,
-    //     )
-    //     .execute(conn.as_mut())
-    //     .await?;
+    pub async fn update(
+        &self,
+        executor: impl Executor<'_, Database = Sqlite>,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"UPDATE agent SET token = ?, name = ? WHERE id = ?;"#,
+            self.token,
+            self.name,
+            self.id,
+        )
+        .execute(executor)
+        .await?;
 
-    //     Ok(())
-    // }
+        Ok(())
+    }
 
     // pub async fn delete(&self, pool: &SqlitePool) -> anyhow::Result<()> {
     //     let mut conn = pool.acquire().await?;
@@ -0,0 +1,188 @@
+use anyhow::Context;
+use scraper::{ElementRef, Html, Node, Selector};
+
+const ANSWER_PREFIX: &str = "Your puzzle answer was";
+
+/// The relevant parts of a saved Advent of Code puzzle page.
+#[derive(Debug)]
+pub struct PuzzlePage {
+    pub part_one: String,
+    pub part_two: Option<String>,
+    /// The "Your puzzle answer was ..." values shown on the page, in part order.
+    pub answers: Vec<String>,
+}
+
+impl PuzzlePage {
+    /// Both parts joined the way the puzzle page shows them.
+    pub fn description(&self) -> String {
+        match &self.part_two {
+            Some(part_two) => format!("{}\n\n{}", self.part_one, part_two),
+            None => self.part_one.clone(),
+        }
+    }
+}
+
+/// Converts the `<article class="day-desc">` blocks of a puzzle page to Markdown. The first
+/// article is Part One, the second (only present once Part One is solved) is Part Two.
+pub fn parse_puzzle_page(html: &str) -> anyhow::Result<PuzzlePage> {
+    let document = Html::parse_document(html);
+    let article_selector = Selector::parse("article.day-desc").unwrap();
+    // The answers follow the articles in `<main>`, outside of them.
+    let answer_selector = Selector::parse("main > p").unwrap();
+    let code_selector = Selector::parse("code").unwrap();
+
+    let mut articles = document
+        .select(&article_selector)
+        .map(|article| strip_answers(&article_to_markdown(article)));
+    let part_one = articles
+        .next()
+        .context("no <article class=\"day-desc\"> found")?;
+    let part_two = articles.next();
+
+    let answers = document
+        .select(&answer_selector)
+        .filter(|paragraph| text_of(*paragraph).trim_start().starts_with(ANSWER_PREFIX))
+        .filter_map(|paragraph| paragraph.select(&code_selector).next())
+        .map(|code| text_of(code).trim().to_string())
+        .collect();
+
+    Ok(PuzzlePage {
+        part_one,
+        part_two,
+        answers,
+    })
+}
+
+/// Removes "Your puzzle answer was ..." lines so solutions don't leak into task descriptions.
+pub fn strip_answers(description: &str) -> String {
+    let lines: Vec<&str> = description
+        .lines()
+        .filter(|line| !line.trim_start().starts_with(ANSWER_PREFIX))
+        .collect();
+    let mut description = lines.join("\n").trim().to_string();
+    while description.contains("\n\n\n") {
+        description = description.replace("\n\n\n", "\n\n");
+    }
+    description
+}
+
+fn article_to_markdown(article: ElementRef) -> String {
+    let blocks: Vec<String> = article
+        .children()
+        .filter_map(ElementRef::wrap)
+        .map(block_to_markdown)
+        .filter(|block| !block.is_empty())
+        .collect();
+    blocks.join("\n\n")
+}
+
+fn block_to_markdown(element: ElementRef) -> String {
+    match element.value().name() {
+        "pre" => format!("```\n{}\n```", text_of(element).trim_end()),
+        "ul" | "ol" => {
+            let items: Vec<String> = element
+                .children()
+                .filter_map(ElementRef::wrap)
+                .map(|item| format!("- {}", collapse_whitespace(&inline_to_markdown(item))))
+                .collect();
+            items.join("\n")
+        }
+        _ => collapse_whitespace(&inline_to_markdown(element)),
+    }
+}
+
+fn inline_to_markdown(element: ElementRef) -> String {
+    let mut markdown = String::new();
+
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => markdown.push_str(text),
+            Node::Element(_) => {
+                let child = ElementRef::wrap(child).unwrap();
+                match child.value().name() {
+                    "code" if has_emphasis(child) => {
+                        markdown.push_str(&format!("*`{}`*", text_of(child)))
+                    }
+                    "code" => markdown.push_str(&format!("`{}`", text_of(child))),
+                    "em" => markdown.push_str(&format!("*{}*", inline_to_markdown(child))),
+                    "br" => markdown.push('\n'),
+                    _ => markdown.push_str(&inline_to_markdown(child)),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    markdown
+}
+
+/// AoC marks the important values (e.g. the answer of an example) as `<code><em>13</em></code>`.
+fn has_emphasis(element: ElementRef) -> bool {
+    element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .any(|child| child.value().name() == "em")
+}
+
+fn text_of(element: ElementRef) -> String {
+    element.text().collect()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOLVED_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en-us">
+<head><title>Day 4 - Advent of Code 2023</title></head>
+<body>
+<header><h1 class="title-global"><a href="/">Advent of Code</a></h1></header>
+<div id="sidebar"><div id="sponsor"><p>Your puzzle answer was <code>sponsored</code>, says our sponsor.</p></div></div>
+<main>
+<article class="day-desc"><h2>--- Day 4: Scratchcards ---</h2><p>The Elf leads you over to the pile of colorful cards.</p>
+<pre><code>Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+</code></pre>
+<p>In the above example, the pile of scratchcards is worth <code><em>13</em></code> points.</p>
+</article>
+<p>Your puzzle answer was <code>21138</code>.</p><article class="day-desc"><h2 id="part2">--- Part Two ---</h2><p>Just as you're about to report your findings to the Elf, <em>one</em> of you realizes the rules have been wrong.</p>
+<ul><li>Card 1 has four matching numbers.</li></ul>
+</article>
+<p>Your puzzle answer was <code>7185540</code>.</p><p class="day-success">Both parts of this puzzle are complete! They provide two gold stars: **</p>
+</main>
+</body>
+</html>"#;
+
+    #[test]
+    fn parses_solved_page() {
+        let page = parse_puzzle_page(SOLVED_PAGE).unwrap();
+
+        assert_eq!(page.answers, ["21138", "7185540"]);
+        assert_eq!(
+            page.part_one,
+            "--- Day 4: Scratchcards ---\n\n\
+             The Elf leads you over to the pile of colorful cards.\n\n\
+             ```\nCard 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53\n```\n\n\
+             In the above example, the pile of scratchcards is worth *`13`* points."
+        );
+        assert_eq!(
+            page.part_two.as_deref(),
+            Some(
+                "--- Part Two ---\n\n\
+                 Just as you're about to report your findings to the Elf, *one* of you realizes \
+                 the rules have been wrong.\n\n\
+                 - Card 1 has four matching numbers."
+            )
+        );
+        assert!(!page.description().contains(ANSWER_PREFIX));
+    }
+
+    #[test]
+    fn strips_answer_lines() {
+        let description = "Part One.\n\nYour puzzle answer was `21138`.\n\n\nPart Two.";
+        assert_eq!(strip_answers(description), "Part One.\n\nPart Two.");
+    }
+}
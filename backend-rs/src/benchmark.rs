@@ -0,0 +1,117 @@
+use std::{io, sync::Arc, time::Duration};
+
+use anyhow::{bail, Context};
+use sandbox::Limits;
+use solver::{Attempt, Solver};
+use tokio::sync::Semaphore;
+
+use crate::check::is_correct;
+
+/// How many benchmarks can wait for the running one. Solutions checked while the queue is full are
+/// not benchmarked.
+const MAX_QUEUED_BENCHMARKS: usize = 16;
+
+/// Measures how fast a correct Rust solution runs: it is built once as a `solver-rs` attempt
+/// (sharing the warm target directory of all attempts) and run several times with the input of
+/// the task.
+#[derive(Debug, Clone)]
+pub struct Benchmarker {
+    pub compile_timeout: Duration,
+    pub runs: usize,
+    pub limits: Limits,
+    solver: Arc<Solver>,
+    // One benchmark at a time, so the measurements don't disturb each other.
+    permit: Arc<Semaphore>,
+    // The running benchmark and the waiting ones.
+    queue: Arc<Semaphore>,
+}
+
+#[derive(Debug)]
+pub enum BenchmarkOutcome {
+    /// The median wall time over all runs.
+    Measured { run_time_ms: u64 },
+    /// The code did not build or did not print the solution in every run.
+    Rejected(String),
+}
+
+impl Benchmarker {
+    pub fn new(solver: Solver, compile_timeout: Duration, runs: usize, limits: Limits) -> Self {
+        Self {
+            compile_timeout,
+            runs: runs.max(1),
+            limits,
+            solver: Arc::new(solver),
+            permit: Arc::new(Semaphore::new(1)),
+            queue: Arc::new(Semaphore::new(1 + MAX_QUEUED_BENCHMARKS)),
+        }
+    }
+
+    pub async fn benchmark(
+        &self,
+        code: &str,
+        input: &str,
+        solution: &str,
+    ) -> anyhow::Result<BenchmarkOutcome> {
+        let Ok(_queued) = self.queue.try_acquire() else {
+            bail!("too many benchmarks queued");
+        };
+        let _permit = self.permit.acquire().await?;
+
+        let benchmarker = self.clone();
+        let code = code.to_string();
+        let input = input.to_string();
+        let solution = solution.to_string();
+        tokio::task::spawn_blocking(move || benchmarker.measure(&code, &input, &solution))
+            .await?
+            .context("cannot benchmark solution")
+    }
+
+    fn measure(&self, code: &str, input: &str, solution: &str) -> io::Result<BenchmarkOutcome> {
+        let attempt = self.solver.create()?;
+        let outcome = self.measure_attempt(&attempt, code, input, solution);
+        // A leftover attempt only takes up disk space, it must not hide the result.
+        let id = attempt.id.clone();
+        if let Err(error) = attempt.remove() {
+            eprintln!("cannot remove attempt {id}: {error}");
+        }
+        outcome
+    }
+
+    fn measure_attempt(
+        &self,
+        attempt: &Attempt,
+        code: &str,
+        input: &str,
+        solution: &str,
+    ) -> io::Result<BenchmarkOutcome> {
+        attempt.write_source(code)?;
+        attempt.write_input(input)?;
+
+        let build = attempt.build(self.compile_timeout)?;
+        if build.timed_out {
+            return Ok(BenchmarkOutcome::Rejected("compilation timed out".into()));
+        }
+        if !build.success {
+            return Ok(BenchmarkOutcome::Rejected("code does not compile".into()));
+        }
+
+        let mut run_times_ms = vec![];
+        for _ in 0..self.runs {
+            let execution = attempt.run(&self.limits)?;
+            if execution.timed_out {
+                return Ok(BenchmarkOutcome::Rejected("code timed out".into()));
+            }
+            if !execution.success() || !is_correct(solution, execution.stdout.trim()) {
+                return Ok(BenchmarkOutcome::Rejected(
+                    "code does not print the solution".into(),
+                ));
+            }
+            run_times_ms.push(execution.wall_time_ms);
+        }
+
+        run_times_ms.sort_unstable();
+        Ok(BenchmarkOutcome::Measured {
+            run_time_ms: run_times_ms[run_times_ms.len() / 2],
+        })
+    }
+}
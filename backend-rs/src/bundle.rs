@@ -0,0 +1,708 @@
+use std::collections::HashMap;
+
+use axum::{extract::Query, Extension, Json};
+use dto::{
+    BundleAgentDto, BundleCompletionDto, BundleDto, BundleTaskDto, ImportBundleReportDto,
+    TaskCreateDto, TaskTypeDto,
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    agent::AgentInDb,
+    completion::CompletionInDb,
+    error::{AppError, DtoValidationError},
+    task::TaskInDb,
+    traits::DtoValidator,
+};
+
+pub const BUNDLE_VERSION: u32 = 1;
+pub const BUNDLE_BODY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Advent of Code year, day and part.
+type Puzzle = (i64, i64, i64);
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub agents: bool,
+    #[serde(default)]
+    pub completions: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+pub async fn export_bundle(
+    Extension(pool): Extension<SqlitePool>,
+    query: Query<ExportQuery>,
+) -> Result<Json<BundleDto>, AppError> {
+    let mut tasks = vec![];
+    for model in TaskInDb::read_all(&pool).await? {
+        let task_type = model.task_type(&pool).await?;
+        tasks.push(BundleTaskDto {
+            id: model.id,
+            task: TaskCreateDto {
+                name: model.name,
+                task_type,
+                solution: model.solution,
+            },
+            archived_at: model.archived_at,
+            aoc_year: model.aoc_year,
+            aoc_day: model.aoc_day,
+            aoc_part: model.aoc_part,
+        });
+    }
+
+    let mut bundle = BundleDto {
+        version: BUNDLE_VERSION,
+        tasks,
+        agents: None,
+        completions: None,
+    };
+
+    if query.agents {
+        let models = AgentInDb::read_all(&pool).await?;
+        bundle.agents = Some(
+            models
+                .into_iter()
+                .map(|model| BundleAgentDto {
+                    id: model.id,
+                    token: model.token,
+                    name: model.name,
+                })
+                .collect(),
+        );
+    }
+
+    if query.completions {
+        let models = CompletionInDb::read_all(&pool).await?;
+        bundle.completions = Some(
+            models
+                .into_iter()
+                .map(|model| BundleCompletionDto {
+                    id: model.id,
+                    task_id: model.task_id,
+                    agent_id: model.agent_id,
+                    start_time: model.start_time,
+                    completion_time: model.completion_time,
+                    best_time_in_ms: model.best_time_in_ms,
+                    best_run_time_in_ms: model.best_run_time_in_ms,
+                })
+                .collect(),
+        );
+    }
+
+    Ok(Json(bundle))
+}
+
+/// Imports a bundle created by `export_bundle`. Records are matched by ID: new ones are created,
+/// existing ones are overwritten. Records clashing with a different record of the database or the
+/// bundle (same agent name, same Advent of Code puzzle, same task and agent of a completion) are
+/// reported as conflicts, in which case nothing is written. With `dry_run` nothing is written
+/// either. The records are written in one transaction.
+pub async fn import_bundle(
+    Extension(pool): Extension<SqlitePool>,
+    query: Query<ImportQuery>,
+    Json(bundle): Json<BundleDto>,
+) -> Result<Json<ImportBundleReportDto>, AppError> {
+    if bundle.version != BUNDLE_VERSION {
+        return Err(AppError::ValidationError(DtoValidationError(format!(
+            "unsupported bundle version {} (must be {})",
+            bundle.version, BUNDLE_VERSION
+        ))));
+    }
+    for task in &bundle.tasks {
+        task.task.validate()?;
+    }
+
+    let existing = ExistingRecords::read(&pool, &bundle).await?;
+    let plan = plan_import(&bundle, &existing, query.dry_run);
+    let mut report = plan.report;
+    if query.dry_run || !report.conflicts.is_empty() {
+        return Ok(Json(report));
+    }
+
+    write(&pool, plan.tasks, plan.agents, plan.completions).await?;
+    report.applied = true;
+
+    Ok(Json(report))
+}
+
+/// The records of the database a bundle is imported into.
+#[derive(Default)]
+struct ExistingRecords {
+    tasks: HashMap<Uuid, TaskInDb>,
+    /// The task types of the existing tasks that are also in the bundle.
+    task_types: HashMap<Uuid, TaskTypeDto>,
+    agents: HashMap<Uuid, AgentInDb>,
+    completions: HashMap<Uuid, CompletionInDb>,
+}
+
+impl ExistingRecords {
+    async fn read(pool: &SqlitePool, bundle: &BundleDto) -> anyhow::Result<Self> {
+        let tasks: HashMap<Uuid, TaskInDb> = TaskInDb::read_all(pool)
+            .await?
+            .into_iter()
+            .map(|model| (model.id, model))
+            .collect();
+        let mut task_types = HashMap::new();
+        for dto in &bundle.tasks {
+            if let Some(model) = tasks.get(&dto.id) {
+                task_types.insert(model.id, model.task_type(pool).await?);
+            }
+        }
+        let agents = AgentInDb::read_all(pool)
+            .await?
+            .into_iter()
+            .map(|model| (model.id, model))
+            .collect();
+        let completions = CompletionInDb::read_all(pool)
+            .await?
+            .into_iter()
+            .map(|model| (model.id, model))
+            .collect();
+
+        Ok(Self {
+            tasks,
+            task_types,
+            agents,
+            completions,
+        })
+    }
+}
+
+/// The records to write for a bundle; the `bool` tells if a record is new.
+struct ImportPlan {
+    report: ImportBundleReportDto,
+    tasks: Vec<(bool, TaskInDb, TaskTypeDto)>,
+    agents: Vec<(bool, AgentInDb)>,
+    completions: Vec<(bool, CompletionInDb)>,
+}
+
+/// Compares the bundle with the existing records, without touching the database.
+fn plan_import(bundle: &BundleDto, existing: &ExistingRecords, dry_run: bool) -> ImportPlan {
+    let mut report = ImportBundleReportDto {
+        dry_run,
+        ..Default::default()
+    };
+
+    // The puzzles and names after the import, to find the ones that would be taken twice.
+    let mut puzzles: HashMap<Uuid, (Option<Puzzle>, &str)> = existing
+        .tasks
+        .values()
+        .map(|model| (model.id, (model_puzzle(model), model.name.as_str())))
+        .collect();
+    for dto in &bundle.tasks {
+        let puzzle = puzzle(dto.aoc_year, dto.aoc_day, dto.aoc_part);
+        puzzles.insert(dto.id, (puzzle, dto.task.name.as_str()));
+    }
+    let mut names: HashMap<Uuid, &str> = existing
+        .agents
+        .values()
+        .map(|model| (model.id, model.name.as_str()))
+        .collect();
+    for dto in bundle.agents.iter().flatten() {
+        names.insert(dto.id, dto.name.as_str());
+    }
+
+    let mut tasks = vec![];
+    for dto in &bundle.tasks {
+        let task_type = dto.task.task_type.clone();
+        let model = TaskInDb {
+            id: dto.id,
+            name: dto.task.name.clone(),
+            task_json: String::new(),
+            solution: dto.task.solution.clone(),
+            archived_at: dto.archived_at,
+            aoc_year: dto.aoc_year,
+            aoc_day: dto.aoc_day,
+            aoc_part: dto.aoc_part,
+        };
+
+        let clash = model_puzzle(&model).and_then(|puzzle| {
+            puzzles
+                .iter()
+                .find(|(id, (other, _))| **id != model.id && *other == Some(puzzle))
+        });
+        if let Some((id, (_, name))) = clash {
+            report.conflicts.push(format!(
+                "task {} ({}): same puzzle as task {id} ({name})",
+                model.id, model.name
+            ));
+            continue;
+        }
+
+        match existing.tasks.get(&model.id) {
+            Some(existing_task) => {
+                let unchanged = existing_task.name == model.name
+                    && existing_task.solution == model.solution
+                    && existing_task.archived_at == model.archived_at
+                    && model_puzzle(existing_task) == model_puzzle(&model)
+                    && existing.task_types.get(&model.id) == Some(&task_type);
+                if unchanged {
+                    report.tasks.unchanged += 1;
+                } else {
+                    report.tasks.updated += 1;
+                    tasks.push((false, model, task_type));
+                }
+            }
+            None => {
+                report.tasks.created += 1;
+                tasks.push((true, model, task_type));
+            }
+        }
+    }
+
+    let mut agents = vec![];
+    for dto in bundle.agents.iter().flatten() {
+        let model = AgentInDb {
+            id: dto.id,
+            token: dto.token,
+            name: dto.name.clone(),
+        };
+
+        let clash = names
+            .iter()
+            .find(|(id, name)| **id != model.id && **name == model.name);
+        if let Some((id, _)) = clash {
+            report.conflicts.push(format!(
+                "agent {} ({}): same name as agent {id}",
+                model.id, model.name
+            ));
+            continue;
+        }
+
+        match existing.agents.get(&model.id) {
+            Some(existing_agent) if *existing_agent == model => report.agents.unchanged += 1,
+            Some(_) => {
+                report.agents.updated += 1;
+                agents.push((false, model));
+            }
+            None => {
+                report.agents.created += 1;
+                agents.push((true, model));
+            }
+        }
+    }
+
+    // The task and agent of every completion after the import, a task can only be started once
+    // per agent.
+    let mut started: HashMap<(Uuid, Uuid), Uuid> = existing
+        .completions
+        .values()
+        .map(|model| ((model.task_id, model.agent_id), model.id))
+        .collect();
+    let mut completions = vec![];
+    for dto in bundle.completions.iter().flatten() {
+        let model = CompletionInDb {
+            id: dto.id,
+            task_id: dto.task_id,
+            agent_id: dto.agent_id,
+            start_time: dto.start_time,
+            completion_time: dto.completion_time,
+            best_time_in_ms: dto.best_time_in_ms,
+            best_run_time_in_ms: dto.best_run_time_in_ms,
+        };
+
+        let task_known = existing.tasks.contains_key(&model.task_id)
+            || tasks.iter().any(|(_, task, _)| task.id == model.task_id);
+        let agent_known = existing.agents.contains_key(&model.agent_id)
+            || agents.iter().any(|(_, agent)| agent.id == model.agent_id);
+        if !task_known || !agent_known {
+            report.conflicts.push(format!(
+                "completion {}: unknown task {} or agent {}",
+                model.id, model.task_id, model.agent_id
+            ));
+            continue;
+        }
+
+        let existing_completion = existing.completions.get(&model.id);
+        if let Some(existing_completion) = existing_completion {
+            let started_by = (existing_completion.task_id, existing_completion.agent_id);
+            if started_by != (model.task_id, model.agent_id) {
+                report.conflicts.push(format!(
+                    "completion {}: exists for task {} and agent {}",
+                    model.id, existing_completion.task_id, existing_completion.agent_id
+                ));
+                continue;
+            }
+        }
+        let other = started
+            .insert((model.task_id, model.agent_id), model.id)
+            .filter(|id| *id != model.id);
+        if let Some(other) = other {
+            report.conflicts.push(format!(
+                "completion {}: task {} was already started by agent {} (completion {other})",
+                model.id, model.task_id, model.agent_id
+            ));
+            continue;
+        }
+
+        match existing_completion {
+            Some(existing_completion) if *existing_completion == model => {
+                report.completions.unchanged += 1
+            }
+            Some(_) => {
+                report.completions.updated += 1;
+                completions.push((false, model));
+            }
+            None => {
+                report.completions.created += 1;
+                completions.push((true, model));
+            }
+        }
+    }
+
+    ImportPlan {
+        report,
+        tasks,
+        agents,
+        completions,
+    }
+}
+
+/// Writes the new and the changed records in one transaction, so a failure leaves nothing
+/// half-imported.
+async fn write(
+    pool: &SqlitePool,
+    tasks: Vec<(bool, TaskInDb, TaskTypeDto)>,
+    agents: Vec<(bool, AgentInDb)>,
+    completions: Vec<(bool, CompletionInDb)>,
+) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await?;
+    for (new, mut model, task_type) in tasks {
+        model.set_task_type(&mut *tx, task_type).await?;
+        if new {
+            model.create(&mut *tx).await?;
+        } else {
+            model.update(&mut *tx).await?;
+        }
+    }
+    for (new, model) in agents {
+        if new {
+            model.create(&mut *tx).await?;
+        } else {
+            model.update(&mut *tx).await?;
+        }
+    }
+    for (new, model) in completions {
+        if new {
+            model.create(&mut *tx).await?;
+        } else {
+            model.update(&mut *tx).await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+fn model_puzzle(model: &TaskInDb) -> Option<Puzzle> {
+    puzzle(model.aoc_year, model.aoc_day, model.aoc_part)
+}
+
+/// Tasks without a complete puzzle don't clash, like in the unique index.
+fn puzzle(year: Option<i64>, day: Option<i64>, part: Option<i64>) -> Option<Puzzle> {
+    Some((year?, day?, part?))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use dto::ImportCountsDto;
+
+    use super::*;
+
+    fn task(name: &str, puzzle: Option<Puzzle>) -> BundleTaskDto {
+        BundleTaskDto {
+            id: Uuid::now_v7(),
+            task: TaskCreateDto {
+                name: name.to_string(),
+                task_type: TaskTypeDto::SimpleTask {
+                    description: format!("Solve {name}"),
+                },
+                solution: "42".to_string(),
+            },
+            archived_at: None,
+            aoc_year: puzzle.map(|(year, _, _)| year),
+            aoc_day: puzzle.map(|(_, day, _)| day),
+            aoc_part: puzzle.map(|(_, _, part)| part),
+        }
+    }
+
+    fn agent(name: &str) -> BundleAgentDto {
+        BundleAgentDto {
+            id: Uuid::now_v7(),
+            token: Uuid::now_v7(),
+            name: name.to_string(),
+        }
+    }
+
+    fn completion(task_id: Uuid, agent_id: Uuid) -> BundleCompletionDto {
+        BundleCompletionDto {
+            id: Uuid::now_v7(),
+            task_id,
+            agent_id,
+            start_time: Utc::now(),
+            completion_time: None,
+            best_time_in_ms: None,
+            best_run_time_in_ms: None,
+        }
+    }
+
+    fn bundle(
+        tasks: Vec<BundleTaskDto>,
+        agents: Vec<BundleAgentDto>,
+        completions: Vec<BundleCompletionDto>,
+    ) -> BundleDto {
+        BundleDto {
+            version: BUNDLE_VERSION,
+            tasks,
+            agents: Some(agents),
+            completions: Some(completions),
+        }
+    }
+
+    /// The database as if `bundle` had been imported into an empty one.
+    fn existing(bundle: &BundleDto) -> ExistingRecords {
+        let plan = plan_import(bundle, &ExistingRecords::default(), false);
+        assert_eq!(plan.report.conflicts, Vec::<String>::new());
+
+        let mut existing = ExistingRecords::default();
+        for (_, model, task_type) in plan.tasks {
+            existing.task_types.insert(model.id, task_type);
+            existing.tasks.insert(model.id, model);
+        }
+        for (_, model) in plan.agents {
+            existing.agents.insert(model.id, model);
+        }
+        for (_, model) in plan.completions {
+            existing.completions.insert(model.id, model);
+        }
+        existing
+    }
+
+    #[test]
+    fn same_puzzle_twice_in_the_bundle_conflicts() {
+        let first = task("first", Some((2023, 1, 1)));
+        let second = task("second", Some((2023, 1, 1)));
+        let other_part = task("other part", Some((2023, 1, 2)));
+        let (first_id, second_id) = (first.id, second.id);
+        let bundle = bundle(vec![first, second, other_part], vec![], vec![]);
+
+        let plan = plan_import(&bundle, &ExistingRecords::default(), false);
+
+        assert_eq!(
+            plan.report.conflicts,
+            vec![
+                format!("task {first_id} (first): same puzzle as task {second_id} (second)"),
+                format!("task {second_id} (second): same puzzle as task {first_id} (first)"),
+            ]
+        );
+        assert_eq!(plan.report.tasks.created, 1);
+    }
+
+    #[test]
+    fn puzzle_of_a_stored_task_conflicts() {
+        let stored = task("stored", Some((2023, 1, 1)));
+        let stored_id = stored.id;
+        let existing = existing(&bundle(vec![stored], vec![], vec![]));
+        let clashing = task("clashing", Some((2023, 1, 1)));
+        let clashing_id = clashing.id;
+
+        let plan = plan_import(&bundle(vec![clashing], vec![], vec![]), &existing, false);
+
+        assert_eq!(
+            plan.report.conflicts,
+            vec![format!(
+                "task {clashing_id} (clashing): same puzzle as task {stored_id} (stored)"
+            )]
+        );
+        assert!(plan.tasks.is_empty());
+    }
+
+    #[test]
+    fn puzzle_moved_away_in_the_same_bundle_does_not_conflict() {
+        let mut stored = task("stored", Some((2023, 1, 1)));
+        let existing = existing(&bundle(vec![stored.clone()], vec![], vec![]));
+        stored.aoc_day = Some(2);
+        let taking_over = task("taking over", Some((2023, 1, 1)));
+
+        let plan = plan_import(
+            &bundle(vec![stored, taking_over], vec![], vec![]),
+            &existing,
+            false,
+        );
+
+        assert_eq!(plan.report.conflicts, Vec::<String>::new());
+        assert_eq!(plan.report.tasks.created, 1);
+        assert_eq!(plan.report.tasks.updated, 1);
+    }
+
+    #[test]
+    fn agent_name_taken_by_another_agent_conflicts() {
+        let stored = agent("alice");
+        let stored_id = stored.id;
+        let existing = existing(&bundle(vec![], vec![stored], vec![]));
+        let clashing = agent("alice");
+        let clashing_id = clashing.id;
+
+        let plan = plan_import(&bundle(vec![], vec![clashing], vec![]), &existing, false);
+
+        assert_eq!(
+            plan.report.conflicts,
+            vec![format!(
+                "agent {clashing_id} (alice): same name as agent {stored_id}"
+            )]
+        );
+        assert!(plan.agents.is_empty());
+    }
+
+    #[test]
+    fn task_started_twice_by_an_agent_conflicts() {
+        let task = task("task", None);
+        let agent = agent("alice");
+        let stored = completion(task.id, agent.id);
+        let (task_id, agent_id, stored_id) = (task.id, agent.id, stored.id);
+        let existing = existing(&bundle(vec![task], vec![agent], vec![stored]));
+        let again = completion(task_id, agent_id);
+        let again_id = again.id;
+
+        let plan = plan_import(&bundle(vec![], vec![], vec![again]), &existing, false);
+
+        assert_eq!(
+            plan.report.conflicts,
+            vec![format!(
+                "completion {again_id}: task {task_id} was already started by agent {agent_id} (completion {stored_id})"
+            )]
+        );
+        assert!(plan.completions.is_empty());
+    }
+
+    #[test]
+    fn task_started_twice_in_the_bundle_conflicts() {
+        let task = task("task", None);
+        let agent = agent("alice");
+        let first = completion(task.id, agent.id);
+        let second = completion(task.id, agent.id);
+        let expected = format!(
+            "completion {}: task {} was already started by agent {} (completion {})",
+            second.id, task.id, agent.id, first.id
+        );
+
+        let plan = plan_import(
+            &bundle(vec![task], vec![agent], vec![first, second]),
+            &ExistingRecords::default(),
+            false,
+        );
+
+        assert_eq!(plan.report.conflicts, vec![expected]);
+        assert_eq!(plan.report.completions.created, 1);
+    }
+
+    #[test]
+    fn completion_moved_to_another_task_conflicts() {
+        let task = task("task", None);
+        let other_task = self::task("other task", None);
+        let agent = agent("alice");
+        let stored = completion(task.id, agent.id);
+        let (task_id, agent_id) = (task.id, agent.id);
+        let mut moved = stored.clone();
+        moved.task_id = other_task.id;
+        let existing = existing(&bundle(vec![task, other_task], vec![agent], vec![stored]));
+
+        let plan = plan_import(
+            &bundle(vec![], vec![], vec![moved.clone()]),
+            &existing,
+            false,
+        );
+
+        assert_eq!(
+            plan.report.conflicts,
+            vec![format!(
+                "completion {}: exists for task {task_id} and agent {agent_id}",
+                moved.id
+            )]
+        );
+    }
+
+    #[test]
+    fn completion_of_unknown_task_or_agent_conflicts() {
+        let task = task("task", None);
+        let agent = agent("alice");
+        let unknown = Uuid::now_v7();
+        let unknown_task = completion(unknown, agent.id);
+        let unknown_agent = completion(task.id, unknown);
+        let message = |dto: &BundleCompletionDto| {
+            format!(
+                "completion {}: unknown task {} or agent {}",
+                dto.id, dto.task_id, dto.agent_id
+            )
+        };
+        let expected = vec![message(&unknown_task), message(&unknown_agent)];
+
+        let plan = plan_import(
+            &bundle(vec![task], vec![agent], vec![unknown_task, unknown_agent]),
+            &ExistingRecords::default(),
+            false,
+        );
+
+        assert_eq!(plan.report.conflicts, expected);
+        assert_eq!(plan.report.tasks.created, 1);
+        assert_eq!(plan.report.agents.created, 1);
+        assert!(plan.completions.is_empty());
+    }
+
+    #[test]
+    fn dry_run_reports_what_would_change() {
+        let unchanged = task("unchanged", Some((2023, 1, 1)));
+        let mut updated = task("updated", Some((2023, 1, 2)));
+        let alice = agent("alice");
+        let existing = existing(&bundle(
+            vec![unchanged.clone(), updated.clone()],
+            vec![alice.clone()],
+            vec![],
+        ));
+        updated.task.solution = "43".to_string();
+        let created = task("created", None);
+        let bob = agent("bob");
+        let started = completion(created.id, bob.id);
+
+        let plan = plan_import(
+            &bundle(
+                vec![unchanged, updated, created],
+                vec![alice, bob],
+                vec![started],
+            ),
+            &existing,
+            true,
+        );
+
+        let counts = |created, updated, unchanged| ImportCountsDto {
+            created,
+            updated,
+            unchanged,
+        };
+        assert_eq!(
+            plan.report,
+            ImportBundleReportDto {
+                dry_run: true,
+                applied: false,
+                tasks: counts(1, 1, 1),
+                agents: counts(1, 0, 1),
+                completions: counts(1, 0, 0),
+                conflicts: vec![],
+            }
+        );
+        let writes: Vec<_> = plan
+            .tasks
+            .iter()
+            .map(|(new, model, _)| (*new, model.name.as_str()))
+            .collect();
+        assert_eq!(writes, vec![(false, "updated"), (true, "created")]);
+    }
+}
@@ -2,30 +2,21 @@ use axum::{
     extract::{Path, Query},
     Extension, Json,
 };
-use serde::{Deserialize, Serialize};
+use dto::{CheckTaskRequestDto, CheckTaskResponseDto, TaskTypeDto};
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
 use crate::{
     agent::AgentInDb,
+    benchmark::{BenchmarkOutcome, Benchmarker},
+    code_runner::DEFAULT_RUNTIME,
     completion::CompletionInDb,
     error::{AppError, DtoValidationError},
+    submission::SubmissionInDb,
     task::{AgentToken, TaskInDb},
     traits::DtoValidator,
 };
 
-#[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CheckTaskRequestDto {
-    pub solution: String,
-}
-
-#[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CheckTaskResponseDto {
-    pub correct: bool,
-}
-
 impl DtoValidator for CheckTaskRequestDto {
     fn validate(&self) -> Result<(), DtoValidationError> {
         if self.solution.len() > 32768 {
@@ -33,12 +24,18 @@ impl DtoValidator for CheckTaskRequestDto {
                 "solution too long (must be <=32768)".into(),
             ));
         }
+        if self.code.as_ref().is_some_and(|code| code.len() > 65536) {
+            return Err(DtoValidationError("code too long (must be <=65536)".into()));
+        }
         Ok(())
     }
 }
 
+/// Compares the solution with the one of the task. If the solution is correct and code is given,
+/// the code is benchmarked in the background (see `benchmark_submission`).
 pub async fn check_task(
     Extension(pool): Extension<SqlitePool>,
+    Extension(benchmarker): Extension<Benchmarker>,
     Path((agent_id, task_id)): Path<(Uuid, Uuid)>,
     token: Query<AgentToken>,
     Json(dto): Json<CheckTaskRequestDto>,
@@ -48,13 +45,41 @@ pub async fn check_task(
         return Err(AppError::Unauthorized);
     }
     dto.validate()?;
-    let task = TaskInDb::read(&pool, task_id).await?;
-    let correct = task.solution == dto.solution;
+    let task = TaskInDb::read_active(&pool, task_id).await?;
+    let task_type: TaskTypeDto = serde_json::from_str(&task.task_json).unwrap();
+    if let TaskTypeDto::CodeSubmission { .. } = task_type {
+        return Err(AppError::ValidationError(DtoValidationError(
+            "task must be solved with a code submission".into(),
+        )));
+    }
+    let correct = is_correct(&task.solution, &dto.solution);
 
     if let Some(completion) = &mut CompletionInDb::read_by(&pool, task_id, agent_id).await? {
+        let mut submission = SubmissionInDb::new(task_id, agent_id, dto.solution, correct);
+        if let Some(code) = dto.code {
+            submission.code = Some(code);
+            submission.runtime = Some(DEFAULT_RUNTIME.to_string());
+        }
+        submission.create(&pool).await?;
+
         if correct {
             completion.complete();
             completion.update(&pool).await?;
+            if submission.code.is_some() {
+                let input = task
+                    .task_type(&pool)
+                    .await?
+                    .take_input()
+                    .unwrap_or_default();
+                tokio::spawn(benchmark_submission(
+                    pool,
+                    benchmarker,
+                    completion.id,
+                    submission,
+                    input,
+                    task.solution,
+                ));
+            }
         }
     } else {
         return Err(AppError::ValidationError(DtoValidationError(
@@ -62,5 +87,43 @@ pub async fn check_task(
         )));
     }
 
-    Ok(Json(CheckTaskResponseDto { correct }))
+    Ok(Json(CheckTaskResponseDto::new(correct)))
+}
+
+/// Benchmarks the code of a correct submission, after the check was answered. The result is kept
+/// on the submission, and a run time on the completion if it is the fastest one of the agent.
+async fn benchmark_submission(
+    pool: SqlitePool,
+    benchmarker: Benchmarker,
+    completion_id: Uuid,
+    mut submission: SubmissionInDb,
+    input: String,
+    solution: String,
+) {
+    let code = submission.code.as_deref().unwrap_or_default();
+    match benchmarker.benchmark(code, &input, &solution).await {
+        Ok(BenchmarkOutcome::Measured { run_time_ms }) => {
+            submission.run_time_in_ms = Some(run_time_ms as i64)
+        }
+        Ok(BenchmarkOutcome::Rejected(reason)) => submission.benchmark_error = Some(reason),
+        Err(error) => submission.benchmark_error = Some(format!("{error:#}")),
+    }
+
+    let result = async {
+        submission.update_benchmark(&pool).await?;
+        if let Some(run_time_in_ms) = submission.run_time_in_ms {
+            CompletionInDb::record_run_time(&pool, completion_id, run_time_in_ms).await?;
+        }
+        anyhow::Ok(())
+    };
+    if let Err(error) = result.await {
+        eprintln!(
+            "cannot save benchmark of submission {}: {error:#}",
+            submission.id
+        );
+    }
+}
+
+pub fn is_correct(solution: &str, submitted: &str) -> bool {
+    solution == submitted
 }
@@ -0,0 +1,65 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Context;
+use sandbox::{Build, Execution, Registry};
+use tokio::sync::Semaphore;
+
+pub const DEFAULT_RUNTIME: &str = "rust";
+
+/// Builds submitted solutions with one of the runtimes of the registry (each with a template that
+/// reads `input.txt` from the working directory and prints the result) and runs them in the
+/// sandbox.
+#[derive(Debug, Clone)]
+pub struct CodeRunner {
+    pub compile_timeout: Duration,
+    pub registry: Arc<Registry>,
+    permits: Arc<Semaphore>,
+}
+
+#[derive(Debug)]
+pub enum RunOutcome {
+    CompileError(Build),
+    CompileTimeout,
+    Finished(Execution),
+}
+
+impl CodeRunner {
+    pub fn new(compile_timeout: Duration, registry: Registry, parallel_runs: usize) -> Self {
+        Self {
+            compile_timeout,
+            registry: Arc::new(registry),
+            permits: Arc::new(Semaphore::new(parallel_runs)),
+        }
+    }
+
+    /// Builds the source in a fresh directory and runs it with `input` as `input.txt`. At most
+    /// `parallel_runs` submissions are processed at the same time, the others wait. Returns
+    /// `None` if there is no such runtime.
+    pub async fn run(
+        &self,
+        runtime: &str,
+        source: &str,
+        input: &str,
+    ) -> anyhow::Result<Option<RunOutcome>> {
+        let Some(runtime) = self.registry.get(runtime).cloned() else {
+            return Ok(None);
+        };
+        let _permit = self.permits.acquire().await?;
+
+        let source = source.to_string();
+        let input = input.to_string();
+        let compile_timeout = self.compile_timeout;
+        let solve = tokio::task::spawn_blocking(move || {
+            runtime.solve(&source, input.as_bytes(), compile_timeout)
+        })
+        .await?
+        .context("cannot build or run solution")?;
+
+        let outcome = match solve.execution {
+            Some(execution) => RunOutcome::Finished(execution),
+            None if solve.build.timed_out => RunOutcome::CompileTimeout,
+            None => RunOutcome::CompileError(solve.build),
+        };
+        Ok(Some(outcome))
+    }
+}
@@ -0,0 +1,237 @@
+use std::path::{Component, Path as StdPath};
+
+use axum::{
+    extract::{Path, Query},
+    Extension, Json,
+};
+use dto::{
+    CodeSubmissionRequestDto, CodeSubmissionResponseDto, DiagnosticDto, RuntimeDto, SuggestionDto,
+    TaskTypeDto,
+};
+use sandbox::Diagnostic;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    agent::AgentInDb,
+    check::is_correct,
+    code_runner::{CodeRunner, RunOutcome, DEFAULT_RUNTIME},
+    completion::CompletionInDb,
+    error::{AppError, DtoValidationError},
+    submission::SubmissionInDb,
+    task::{AgentToken, TaskInDb},
+    traits::DtoValidator,
+};
+
+const MAX_COMPILE_ERROR_LEN: usize = 32768;
+/// The compile error of a failed build without diagnostics of the submitted code.
+const COMPILE_ERROR: &str = "code does not compile";
+
+impl DtoValidator for CodeSubmissionRequestDto {
+    fn validate(&self) -> Result<(), DtoValidationError> {
+        if self.code.len() > 65536 {
+            return Err(DtoValidationError("code too long (must be <=65536)".into()));
+        }
+        Ok(())
+    }
+}
+
+/// The runtimes (languages) code can be submitted in, with their templates.
+pub async fn read_all_runtimes(
+    Extension(code_runner): Extension<CodeRunner>,
+) -> Json<Vec<RuntimeDto>> {
+    let dtos = code_runner
+        .registry
+        .runtimes
+        .iter()
+        .map(|runtime| RuntimeDto {
+            name: runtime.name.clone(),
+            source_file: runtime.source_file.clone(),
+            template: runtime.template.clone(),
+        })
+        .collect();
+
+    Json(dtos)
+}
+
+/// Builds the submitted source with the chosen runtime, runs it against the hidden input of the
+/// task and compares its output with the solution. Every run (but not a failed build) is stored
+/// as a submission. Of the build, only the diagnostics of the submitted code are returned, and
+/// nothing of the stderr of the program.
+pub async fn submit_code(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(code_runner): Extension<CodeRunner>,
+    Path((agent_id, task_id)): Path<(Uuid, Uuid)>,
+    token: Query<AgentToken>,
+    Json(dto): Json<CodeSubmissionRequestDto>,
+) -> Result<Json<CodeSubmissionResponseDto>, AppError> {
+    let agent = AgentInDb::read(&pool, agent_id).await?;
+    if agent.token != token.token {
+        return Err(AppError::Unauthorized);
+    }
+    dto.validate()?;
+
+    let task = TaskInDb::read_active(&pool, task_id).await?;
+    let task_type = task.task_type(&pool).await?;
+    let TaskTypeDto::CodeSubmission { input, .. } = task_type else {
+        return Err(AppError::ValidationError(DtoValidationError(
+            "task does not accept code submissions".into(),
+        )));
+    };
+    let Some(mut completion) = CompletionInDb::read_by(&pool, task_id, agent_id).await? else {
+        return Err(AppError::ValidationError(DtoValidationError(
+            "task not started".into(),
+        )));
+    };
+
+    if code_runner.registry.runtimes.is_empty() {
+        return Err(AppError::ValidationError(DtoValidationError(
+            "code submissions are disabled".into(),
+        )));
+    }
+    let runtime = dto.runtime.as_deref().unwrap_or(DEFAULT_RUNTIME);
+    let Some(outcome) = code_runner.run(runtime, &dto.code, &input).await? else {
+        return Err(AppError::ValidationError(DtoValidationError(format!(
+            "unknown runtime {runtime}"
+        ))));
+    };
+
+    let mut response = CodeSubmissionResponseDto::default();
+    let output = match outcome {
+        RunOutcome::CompileError(build) => {
+            let diagnostics = own_diagnostics(build.diagnostics);
+            let compile_error = if diagnostics.is_empty() {
+                COMPILE_ERROR.to_string()
+            } else {
+                diagnostics
+                    .iter()
+                    .map(Diagnostic::to_string)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            };
+            response.compile_error =
+                Some(compile_error.chars().take(MAX_COMPILE_ERROR_LEN).collect());
+            response.diagnostics = Some(diagnostics.into_iter().map(diagnostic_dto).collect());
+            return Ok(Json(response));
+        }
+        RunOutcome::CompileTimeout => {
+            response.compile_error = Some("compilation timed out".into());
+            return Ok(Json(response));
+        }
+        RunOutcome::Finished(execution) => {
+            let output = execution.stdout.trim().to_string();
+            response.correct = execution.success() && is_correct(&task.solution, &output);
+            response.timed_out = execution.timed_out;
+            response.exit_code = execution.exit_code;
+            response.output = Some(output.clone());
+            response.wall_time_ms = Some(execution.wall_time_ms);
+            response.cpu_time_ms = Some(execution.cpu_time_ms);
+            output
+        }
+    };
+
+    let mut submission = SubmissionInDb::new(task_id, agent_id, output, response.correct);
+    submission.code = Some(dto.code);
+    submission.runtime = Some(runtime.to_string());
+    submission.create(&pool).await?;
+
+    if response.correct {
+        completion.complete();
+        completion.update(&pool).await?;
+    }
+
+    Ok(Json(response))
+}
+
+/// Only the diagnostics located in the submitted code (`src/...` of the crate). The others, like
+/// the output of the linker or of cargo, can contain anything the build could read.
+fn own_diagnostics(diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    diagnostics
+        .into_iter()
+        .filter(|diagnostic| {
+            diagnostic.file.as_deref().is_some_and(|file| {
+                let path = StdPath::new(file);
+                path.starts_with("src")
+                    && path
+                        .components()
+                        .all(|component| matches!(component, Component::Normal(_)))
+            })
+        })
+        .collect()
+}
+
+fn diagnostic_dto(diagnostic: Diagnostic) -> DiagnosticDto {
+    DiagnosticDto {
+        level: diagnostic.level,
+        code: diagnostic.code,
+        message: diagnostic.message,
+        file: diagnostic.file,
+        line: diagnostic.line,
+        column: diagnostic.column,
+        label: diagnostic.label,
+        notes: diagnostic.notes,
+        suggestions: diagnostic
+            .suggestions
+            .into_iter()
+            .map(|suggestion| SuggestionDto {
+                message: suggestion.message,
+                line: suggestion.line,
+                column: suggestion.column,
+                replacement: suggestion.replacement,
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(file: Option<&str>) -> Diagnostic {
+        Diagnostic {
+            level: "error".into(),
+            code: None,
+            message: "couldn't read file".into(),
+            file: file.map(Into::into),
+            line: file.map(|_| 1),
+            column: file.map(|_| 1),
+            label: None,
+            notes: vec![],
+            suggestions: vec![],
+        }
+    }
+
+    fn files(diagnostics: Vec<Diagnostic>) -> Vec<Option<String>> {
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.file)
+            .collect()
+    }
+
+    #[test]
+    fn keeps_diagnostics_of_submitted_code() {
+        let diagnostics = vec![
+            diagnostic(Some("src/main.rs")),
+            diagnostic(Some("src/solution/mod.rs")),
+        ];
+        assert_eq!(
+            files(own_diagnostics(diagnostics)),
+            [
+                Some("src/main.rs".into()),
+                Some("src/solution/mod.rs".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_other_diagnostics() {
+        let diagnostics = vec![
+            diagnostic(None),
+            diagnostic(Some("/root/.cargo/registry/src/lib.rs")),
+            diagnostic(Some("src/../../secret.rs")),
+            diagnostic(Some("../src/main.rs")),
+            diagnostic(Some("build.rs")),
+        ];
+        assert!(own_diagnostics(diagnostics).is_empty());
+    }
+}
@@ -1,11 +1,11 @@
 use axum::{Extension, Json};
 use chrono::{DateTime, Utc};
-use sqlx::{FromRow, SqlitePool};
+use sqlx::{Executor, FromRow, Sqlite, SqlitePool};
 use uuid::Uuid;
 
-use crate::error::AppError;
+use crate::{error::AppError, submission::SubmissionInDb};
 
-#[derive(FromRow, Debug)]
+#[derive(FromRow, Debug, PartialEq)]
 pub struct CompletionInDb {
     pub id: Uuid,
     pub task_id: Uuid,
@@ -13,15 +13,25 @@ pub struct CompletionInDb {
     pub start_time: DateTime<Utc>,
     pub completion_time: Option<DateTime<Utc>>,
     pub best_time_in_ms: Option<i64>,
+    /// Median run time of the fastest benchmarked solution.
+    pub best_run_time_in_ms: Option<i64>,
 }
 
 pub async fn delete_all_completions(
     Extension(pool): Extension<SqlitePool>,
 ) -> Result<Json<String>, AppError> {
-    CompletionInDb::delete_all(&pool).await?;
+    delete_completions_and_submissions(&pool).await?;
     Ok(Json("All completions deleted".to_string()))
 }
 
+async fn delete_completions_and_submissions(pool: &SqlitePool) -> anyhow::Result<()> {
+    let mut tx = pool.begin().await?;
+    CompletionInDb::delete_all(&mut *tx).await?;
+    SubmissionInDb::delete_all(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
 impl CompletionInDb {
     pub fn new(task_id: Uuid, agent_id: Uuid) -> Self {
         let id = Uuid::now_v7();
@@ -34,11 +44,15 @@ impl CompletionInDb {
             start_time,
             completion_time: None,
             best_time_in_ms: None,
+            best_run_time_in_ms: None,
         }
     }
 
     pub fn complete(&mut self) {
-        let completion_time = Utc::now();
+        self.complete_at(Utc::now());
+    }
+
+    pub fn complete_at(&mut self, completion_time: DateTime<Utc>) {
         let duration = (completion_time - self.start_time).num_milliseconds();
 
         let best_time_in_ms = self
@@ -50,18 +64,21 @@ impl CompletionInDb {
         self.best_time_in_ms = Some(best_time_in_ms);
     }
 
-    pub async fn create(&self, pool: &SqlitePool) -> anyhow::Result<()> {
-        let mut conn = pool.acquire().await?;
+    pub async fn create(
+        &self,
+        executor: impl Executor<'_, Database = Sqlite>,
+    ) -> anyhow::Result<()> {
         sqlx::query!(
-            r#"INSERT INTO completion (id, task_id, agent_id, start_time, completion_time, best_time_in_ms) VALUES (?, ?, ?, ?, ?, ?);"#,
+            r#"INSERT INTO completion (id, task_id, agent_id, start_time, completion_time, best_time_in_ms, best_run_time_in_ms) VALUES (?, ?, ?, ?, ?, ?, ?);"#,
             self.id,
             self.task_id,
             self.agent_id,
             self.start_time,
             self.completion_time,
             self.best_time_in_ms,
+            self.best_run_time_in_ms,
         )
-        .execute(conn.as_mut())
+        .execute(executor)
         .await?;
 
         Ok(())
@@ -71,7 +88,7 @@ impl CompletionInDb {
         let mut conn = pool.acquire().await?;
         let model = sqlx::query_as!(
             Self,
-            r#"SELECT id as "id: uuid::Uuid", task_id as "task_id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", start_time as "start_time: chrono::DateTime<Utc>", completion_time as "completion_time: chrono::DateTime<Utc>", best_time_in_ms FROM completion WHERE id = ?;"#,
+            r#"SELECT id as "id: uuid::Uuid", task_id as "task_id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", start_time as "start_time: chrono::DateTime<Utc>", completion_time as "completion_time: chrono::DateTime<Utc>", best_time_in_ms, best_run_time_in_ms FROM completion WHERE id = ?;"#,
             id,
         )
         .fetch_one(conn.as_mut())
@@ -87,7 +104,7 @@ impl CompletionInDb {
         let mut conn = pool.acquire().await?;
         let model = sqlx::query_as!(
             Self,
-            r#"SELECT id as "id: uuid::Uuid", task_id as "task_id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", start_time as "start_time: chrono::DateTime<Utc>", completion_time as "completion_time: chrono::DateTime<Utc>", best_time_in_ms FROM completion WHERE task_id = ? AND agent_id = ?;"#,
+            r#"SELECT id as "id: uuid::Uuid", task_id as "task_id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", start_time as "start_time: chrono::DateTime<Utc>", completion_time as "completion_time: chrono::DateTime<Utc>", best_time_in_ms, best_run_time_in_ms FROM completion WHERE task_id = ? AND agent_id = ?;"#,
             task_id,
             agent_id,
         )
@@ -96,28 +113,65 @@ impl CompletionInDb {
         Ok(model)
     }
 
-    // pub async fn read_all(pool: &SqlitePool) -> anyhow::Result<Vec<Self>> {
-    //     let mut conn = pool.acquire().await?;
-    //     let models = sqlx::query_as!(
-    //         Self,
-    //         r#"SELECT id as "id: uuid::Uuid", task_id as "task_id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", start_time as "start_time: chrono::DateTime<Utc>", completion_time as "completion_time: chrono::DateTime<Utc>", best_time_in_ms FROM completion;"#,
-    //     )
-    //     .fetch_all(conn.as_mut())
-    //     .await?;
-    //     Ok(models)
-    // }
+    pub async fn read_all_by_task(
+        executor: impl Executor<'_, Database = Sqlite>,
+        task_id: Uuid,
+    ) -> anyhow::Result<Vec<Self>> {
+        let models = sqlx::query_as!(
+            Self,
+            r#"SELECT id as "id: uuid::Uuid", task_id as "task_id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", start_time as "start_time: chrono::DateTime<Utc>", completion_time as "completion_time: chrono::DateTime<Utc>", best_time_in_ms, best_run_time_in_ms FROM completion WHERE task_id = ?;"#,
+            task_id,
+        )
+        .fetch_all(executor)
+        .await?;
+        Ok(models)
+    }
 
-    pub async fn update(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+    pub async fn read_all(pool: &SqlitePool) -> anyhow::Result<Vec<Self>> {
         let mut conn = pool.acquire().await?;
+        let models = sqlx::query_as!(
+            Self,
+            r#"SELECT id as "id: uuid::Uuid", task_id as "task_id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", start_time as "start_time: chrono::DateTime<Utc>", completion_time as "completion_time: chrono::DateTime<Utc>", best_time_in_ms, best_run_time_in_ms FROM completion;"#,
+        )
+        .fetch_all(conn.as_mut())
+        .await?;
+        Ok(models)
+    }
+
+    pub async fn update(
+        &self,
+        executor: impl Executor<'_, Database = Sqlite>,
+    ) -> anyhow::Result<()> {
         sqlx::query!(
-            r#"UPDATE completion SET task_id = ?, agent_id = ?, start_time = ?, completion_time = ?, best_time_in_ms = ? WHERE id = ?;"#,
+            r#"UPDATE completion SET task_id = ?, agent_id = ?, start_time = ?, completion_time = ?, best_time_in_ms = ?, best_run_time_in_ms = ? WHERE id = ?;"#,
             self.task_id,
             self.agent_id,
             self.start_time,
             self.completion_time,
             self.best_time_in_ms,
+            self.best_run_time_in_ms,
             self.id,
         )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Keeps the run time if it is faster than the best one, unless the completion was revoked in
+    /// the meantime. Benchmarks finish in the background, so this only touches that column.
+    pub async fn record_run_time(
+        pool: &SqlitePool,
+        id: Uuid,
+        run_time_in_ms: i64,
+    ) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(
+            r#"UPDATE completion SET best_run_time_in_ms = MIN(COALESCE(best_run_time_in_ms, ?), ?) WHERE id = ? AND completion_time IS NOT NULL;"#,
+            run_time_in_ms,
+            run_time_in_ms,
+            id,
+        )
         .execute(conn.as_mut())
         .await?;
 
@@ -133,10 +187,9 @@ impl CompletionInDb {
     //     Ok(())
     // }
 
-    pub async fn delete_all(pool: &SqlitePool) -> anyhow::Result<()> {
-        let mut conn = pool.acquire().await?;
+    pub async fn delete_all(executor: impl Executor<'_, Database = Sqlite>) -> anyhow::Result<()> {
         sqlx::query!(r#"DELETE FROM completion;"#)
-            .execute(conn.as_mut())
+            .execute(executor)
             .await?;
 
         Ok(())
@@ -2,6 +2,7 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use dto::ExamplesNotSupported;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -22,14 +23,28 @@ impl IntoResponse for AppError {
             }
             AppError::ValidationError(v) => v.into_response(),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized").into_response(),
+            AppError::TooManyRequests => {
+                (StatusCode::TOO_MANY_REQUESTS, "Too many requests").into_response()
+            }
+            AppError::BudgetExceeded => {
+                (StatusCode::PAYMENT_REQUIRED, "LLM token budget exhausted").into_response()
+            }
         }
     }
 }
 
+#[derive(Error, Debug)]
 pub enum AppError {
+    #[error("{0}")]
     InternalServerError(anyhow::Error),
+    #[error(transparent)]
     ValidationError(DtoValidationError),
+    #[error("Unauthorized")]
     Unauthorized,
+    #[error("Too many requests")]
+    TooManyRequests,
+    #[error("LLM token budget exhausted")]
+    BudgetExceeded,
 }
 
 impl From<anyhow::Error> for AppError {
@@ -38,6 +53,18 @@ impl From<anyhow::Error> for AppError {
     }
 }
 
+impl From<ExamplesNotSupported> for DtoValidationError {
+    fn from(inner: ExamplesNotSupported) -> Self {
+        DtoValidationError(inner.to_string())
+    }
+}
+
+impl From<ExamplesNotSupported> for AppError {
+    fn from(inner: ExamplesNotSupported) -> Self {
+        AppError::ValidationError(inner.into())
+    }
+}
+
 impl From<DtoValidationError> for AppError {
     fn from(inner: DtoValidationError) -> Self {
         AppError::ValidationError(inner)
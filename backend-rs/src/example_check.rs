@@ -0,0 +1,62 @@
+use axum::{
+    extract::{Path, Query},
+    Extension, Json,
+};
+use dto::{CheckTaskResponseDto, ExampleCheckRequestDto, TaskTypeDto};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    agent::AgentInDb,
+    check::is_correct,
+    error::{AppError, DtoValidationError},
+    rate_limit::RateLimiter,
+    task::{AgentToken, TaskInDb},
+    traits::DtoValidator,
+};
+
+/// Rate limiter of the example checks, separate from anything affecting the real submissions.
+#[derive(Debug, Clone)]
+pub struct ExampleCheckRateLimiter(pub RateLimiter);
+
+impl DtoValidator for ExampleCheckRequestDto {
+    fn validate(&self) -> Result<(), DtoValidationError> {
+        if self.solution.len() > 32768 {
+            return Err(DtoValidationError(
+                "solution too long (must be <=32768)".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Checks a solution against one of the examples of a task (by index). Does not start the task,
+/// complete it or record a submission.
+pub async fn check_example(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(rate_limiter): Extension<ExampleCheckRateLimiter>,
+    Path((agent_id, task_id)): Path<(Uuid, Uuid)>,
+    token: Query<AgentToken>,
+    Json(dto): Json<ExampleCheckRequestDto>,
+) -> Result<Json<CheckTaskResponseDto>, AppError> {
+    let agent = AgentInDb::read(&pool, agent_id).await?;
+    if agent.token != token.token {
+        return Err(AppError::Unauthorized);
+    }
+    dto.validate()?;
+    if !rate_limiter.0.try_acquire(agent_id) {
+        return Err(AppError::TooManyRequests);
+    }
+
+    let task = TaskInDb::read_active(&pool, task_id).await?;
+    let task_type: TaskTypeDto = serde_json::from_str(&task.task_json).unwrap();
+    let Some(example) = task_type.examples().get(dto.example) else {
+        return Err(AppError::ValidationError(DtoValidationError(format!(
+            "task has no example {}",
+            dto.example
+        ))));
+    };
+
+    let correct = is_correct(&example.expected_output, &dto.solution);
+    Ok(Json(CheckTaskResponseDto::new(correct)))
+}
@@ -0,0 +1,241 @@
+use anyhow::Context;
+use axum::{extract::Path, Extension, Json};
+use dto::{TaskExampleDto, TaskTypeDto};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{error::AppError, import::PART_TWO_MARKER, task::TaskInDb, traits::DtoValidator};
+
+enum Segment {
+    Code(String),
+    Text(String),
+}
+
+pub async fn suggest_task_examples(
+    Extension(pool): Extension<SqlitePool>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<Vec<TaskExampleDto>>, AppError> {
+    let task = TaskInDb::read(&pool, task_id).await?;
+    let task_type: TaskTypeDto =
+        serde_json::from_str(&task.task_json).context("invalid task JSON")?;
+
+    Ok(Json(suggest_examples(task_type.description())))
+}
+
+pub async fn add_task_examples(
+    Extension(pool): Extension<SqlitePool>,
+    Path(task_id): Path<Uuid>,
+    Json(examples): Json<Vec<TaskExampleDto>>,
+) -> Result<Json<Vec<TaskExampleDto>>, AppError> {
+    let examples = add_examples(&pool, task_id, examples).await?;
+    Ok(Json(examples))
+}
+
+/// Appends the examples to the task, skipping ones it already has. Returns all examples of the task.
+pub async fn add_examples(
+    pool: &SqlitePool,
+    task_id: Uuid,
+    new_examples: Vec<TaskExampleDto>,
+) -> Result<Vec<TaskExampleDto>, AppError> {
+    let mut task = TaskInDb::read(pool, task_id).await?;
+    let mut task_type: TaskTypeDto =
+        serde_json::from_str(&task.task_json).context("invalid task JSON")?;
+
+    let mut examples = task_type.examples().to_vec();
+    for example in new_examples {
+        if !examples.contains(&example) {
+            examples.push(example);
+        }
+    }
+    examples.validate()?;
+
+    task_type.set_examples(examples.clone())?;
+    task.task_json = serde_json::to_string(&task_type).context("failed to serialize task")?;
+    task.update(pool).await?;
+
+    Ok(examples)
+}
+
+/// Proposes examples from a Markdown puzzle description: the first code block after an answer is
+/// taken as example input, the last emphasized value (`` *`13`* `` or `**13**`) in the text
+/// following it as the expected output. If Part Two has no code block of its own, its answer is
+/// paired with the last input of Part One ("Using the same example ...").
+pub fn suggest_examples(description: &str) -> Vec<TaskExampleDto> {
+    let mut examples = vec![];
+    let mut pending_input: Option<String> = None;
+    let mut last_input: Option<String> = None;
+    let mut part_two_has_input = false;
+
+    for (part, segment) in split_segments(description) {
+        match segment {
+            Segment::Code(code) => {
+                if part == 2 {
+                    part_two_has_input = true;
+                }
+                pending_input.get_or_insert(code);
+            }
+            Segment::Text(text) => {
+                let Some(answer) = emphasized_values(&text).pop() else {
+                    continue;
+                };
+
+                if let Some(input) = pending_input.take() {
+                    examples.push(TaskExampleDto {
+                        part,
+                        input: input.clone(),
+                        expected_output: answer,
+                    });
+                    last_input = Some(input);
+                } else if part == 2 && !part_two_has_input {
+                    if let Some(input) = last_input.take() {
+                        examples.push(TaskExampleDto {
+                            part,
+                            input,
+                            expected_output: answer,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    examples
+}
+
+fn split_segments(description: &str) -> Vec<(u8, Segment)> {
+    let mut segments = vec![];
+    let mut part = 1;
+    let mut current = String::new();
+    let mut in_code = false;
+
+    for line in description.lines() {
+        if line.trim_start().starts_with("```") {
+            if in_code {
+                segments.push((part, Segment::Code(current)));
+            } else {
+                segments.push((part, Segment::Text(current)));
+            }
+            current = String::new();
+            in_code = !in_code;
+            continue;
+        }
+
+        if !in_code && line.contains(PART_TWO_MARKER) {
+            segments.push((part, Segment::Text(current)));
+            current = String::new();
+            part = 2;
+        }
+
+        current.push_str(line);
+        current.push('\n');
+    }
+    segments.push((part, Segment::Text(current)));
+
+    segments
+}
+
+fn emphasized_values(text: &str) -> Vec<String> {
+    let mut values = vec![];
+
+    for (open, close) in [("*`", "`*"), ("**", "**")] {
+        let mut rest = text;
+        while let Some(start) = rest.find(open) {
+            rest = &rest[start + open.len()..];
+            let Some(end) = rest.find(close) else {
+                break;
+            };
+            let value = rest[..end].trim_matches('`').trim();
+            if !value.is_empty() && !value.contains(char::is_whitespace) {
+                values.push((text.len() - rest.len(), value.to_string()));
+            }
+            rest = &rest[end + close.len()..];
+        }
+    }
+
+    values.sort();
+    values.into_iter().map(|(_, value)| value).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Day 4 of 2023, shortened.
+    const DESCRIPTION: &str = "\
+--- Day 4: Scratchcards ---
+
+The Elf leads you over to the pile of colorful cards. For example:
+
+```
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+```
+
+In the above example, card 1 has four winning numbers, so it is worth **8** points. So, in this \
+example, the Elf's pile of scratchcards is worth *`13`* points.
+
+--- Part Two ---
+
+Just as you're about to report your findings to the Elf, one of you realizes that the rules have \
+actually been printed on the back of every card this whole time.
+
+Once all of the originals and copies have been processed, you end up with *`30`* scratchcards.
+";
+
+    const INPUT: &str = "\
+Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+";
+
+    fn example(part: u8, input: &str, expected_output: &str) -> TaskExampleDto {
+        TaskExampleDto {
+            part,
+            input: input.into(),
+            expected_output: expected_output.into(),
+        }
+    }
+
+    #[test]
+    fn part_two_reuses_input_of_part_one() {
+        assert_eq!(
+            suggest_examples(DESCRIPTION),
+            [example(1, INPUT, "13"), example(2, INPUT, "30")]
+        );
+    }
+
+    #[test]
+    fn part_two_with_own_input() {
+        let description = DESCRIPTION.replace(
+            "Once all",
+            "For example:\n\n```\nCard 3: 1 | 1\n```\n\nOnce all",
+        );
+        assert_eq!(
+            suggest_examples(&description),
+            [example(1, INPUT, "13"), example(2, "Card 3: 1 | 1\n", "30")]
+        );
+    }
+
+    #[test]
+    fn part_one_only() {
+        let part_one = DESCRIPTION.split(PART_TWO_MARKER).next().unwrap();
+        assert_eq!(suggest_examples(part_one), [example(1, INPUT, "13")]);
+    }
+
+    #[test]
+    fn splits_segments_by_part() {
+        let segments: Vec<(u8, bool)> = split_segments(DESCRIPTION)
+            .into_iter()
+            .map(|(part, segment)| (part, matches!(segment, Segment::Code(_))))
+            .collect();
+        assert_eq!(segments, [(1, false), (1, true), (1, false), (2, false)]);
+    }
+
+    #[test]
+    fn ignores_marker_in_code() {
+        let description = "```\n--- Part Two ---\n```\nThe answer is *`1`*.\n";
+        assert_eq!(
+            suggest_examples(description),
+            [example(1, "--- Part Two ---\n", "1")]
+        );
+    }
+}
@@ -1,12 +1,11 @@
-use axum::{response::IntoResponse, Json};
+use axum::Json;
+use dto::HealthDto;
 
-pub async fn health_checker_handler() -> impl IntoResponse {
+pub async fn health_checker_handler() -> Json<HealthDto> {
     const MESSAGE: &str = "SWEX Camp 2024 - Advent of AI: Meta hackathon backend";
 
-    let json_response = serde_json::json!({
-        "status": "success",
-        "message": MESSAGE
-    });
-
-    Json(json_response)
+    Json(HealthDto {
+        status: "success".into(),
+        message: MESSAGE.into(),
+    })
 }
@@ -0,0 +1,351 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use axum::{Extension, Json};
+use dto::{ImportReportDto, ImportRequestDto, TaskCreateDto, TaskTypeDto};
+use sqlx::SqlitePool;
+
+use crate::{
+    aoc_html::{parse_puzzle_page, strip_answers},
+    error::{AppError, DtoValidationError},
+    task::TaskInDb,
+    traits::DtoValidator,
+};
+
+/// The heading Part Two of a puzzle description starts with.
+pub const PART_TWO_MARKER: &str = "--- Part Two ---";
+
+/// One part of an Advent of Code puzzle, ready to be stored as a task.
+#[derive(Debug)]
+pub struct AdventOfCodeTask {
+    pub year: i64,
+    pub day: i64,
+    pub part: i64,
+    pub dto: TaskCreateDto,
+}
+
+impl AdventOfCodeTask {
+    pub fn new(
+        year: i64,
+        day: i64,
+        part: i64,
+        description: String,
+        input: String,
+        solution: String,
+    ) -> Self {
+        let (part_name, task_type) = if part == 1 {
+            (
+                "One",
+                TaskTypeDto::AdventOfCodePartOne {
+                    description,
+                    input,
+                    examples: vec![],
+                },
+            )
+        } else {
+            (
+                "Two",
+                TaskTypeDto::AdventOfCodePartTwo {
+                    description,
+                    input,
+                    examples: vec![],
+                },
+            )
+        };
+
+        Self {
+            year,
+            day,
+            part,
+            dto: TaskCreateDto {
+                name: format!("Advent of code {year}, Day {day}, Part {part_name}"),
+                task_type,
+                solution,
+            },
+        }
+    }
+}
+
+pub async fn import_tasks_from_dir(
+    Extension(pool): Extension<SqlitePool>,
+    Json(dto): Json<ImportRequestDto>,
+) -> Result<Json<ImportReportDto>, AppError> {
+    let tasks = tokio::task::spawn_blocking(move || read_puzzle_dir(Path::new(&dto.path)))
+        .await
+        .context("cannot read puzzles")??;
+    validate_all(&tasks)?;
+    let report = import_tasks(&pool, tasks).await?;
+
+    Ok(Json(report))
+}
+
+pub fn validate_all(tasks: &[AdventOfCodeTask]) -> Result<(), DtoValidationError> {
+    for task in tasks {
+        task.dto
+            .validate()
+            .map_err(|e| DtoValidationError(format!("{}: {}", task.dto.name, e.0)))?;
+    }
+    Ok(())
+}
+
+/// Creates or updates the task of every puzzle part, matched by year, day and part. Examples of
+/// existing tasks are kept unless the imported task brings its own. Nothing is imported if one of
+/// the tasks cannot be stored.
+pub async fn import_tasks(
+    pool: &SqlitePool,
+    tasks: Vec<AdventOfCodeTask>,
+) -> anyhow::Result<ImportReportDto> {
+    let mut report = ImportReportDto::default();
+    let mut tx = pool.begin().await?;
+
+    for mut task in tasks {
+        match TaskInDb::read_by_puzzle(&mut *tx, task.year, task.day, task.part).await? {
+            Some(mut model) => {
+                let existing_task_type = model.task_type(&mut *tx).await?;
+                if task.dto.task_type.examples().is_empty() {
+                    let examples = existing_task_type.examples().to_vec();
+                    task.dto.task_type.set_examples(examples)?;
+                }
+
+                if model.name == task.dto.name
+                    && existing_task_type == task.dto.task_type
+                    && model.solution == task.dto.solution
+                {
+                    report.unchanged.push(model.id);
+                    continue;
+                }
+
+                model.name = task.dto.name;
+                model.set_task_type(&mut *tx, task.dto.task_type).await?;
+                model.solution = task.dto.solution;
+                model.update(&mut *tx).await?;
+                report.updated.push(model.id);
+            }
+            None => {
+                let mut model = TaskInDb::new(task.dto.name, String::new(), task.dto.solution);
+                model.set_task_type(&mut *tx, task.dto.task_type).await?;
+                model.aoc_year = Some(task.year);
+                model.aoc_day = Some(task.day);
+                model.aoc_part = Some(task.part);
+                model.create(&mut *tx).await?;
+                report.created.push(model.id);
+            }
+        }
+    }
+
+    tx.commit().await?;
+    Ok(report)
+}
+
+/// Reads a directory laid out as `<year>/day<day>/{description.md,input.txt,answer1.txt,answer2.txt}`.
+/// Instead of `description.md`, a saved puzzle page `puzzle.html` can be used; answers shown on the
+/// page are used for parts without an answer file. A part is only imported if its answer is known.
+/// Part One gets the description up to the "--- Part Two ---" heading, Part Two gets the whole
+/// description.
+pub fn read_puzzle_dir(root: &Path) -> anyhow::Result<Vec<AdventOfCodeTask>> {
+    let mut tasks = vec![];
+
+    for (year, year_dir) in numbered_subdirs(root, "")? {
+        for (day, day_dir) in numbered_subdirs(&year_dir, "day")? {
+            let (description, page_answers) = read_description(&day_dir)?;
+            let input = read_file(&day_dir.join("input.txt"))?;
+            let answer = |part: usize| -> anyhow::Result<Option<String>> {
+                let answer_file = day_dir.join(format!("answer{part}.txt"));
+                Ok(read_answer(&answer_file)?.or_else(|| page_answers.get(part - 1).cloned()))
+            };
+
+            if let Some(answer) = answer(1)? {
+                let part_one = description
+                    .split(PART_TWO_MARKER)
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string();
+                tasks.push(AdventOfCodeTask::new(
+                    year,
+                    day,
+                    1,
+                    part_one,
+                    input.clone(),
+                    answer,
+                ));
+            }
+
+            if let Some(answer) = answer(2)? {
+                tasks.push(AdventOfCodeTask::new(
+                    year,
+                    day,
+                    2,
+                    description,
+                    input,
+                    answer,
+                ));
+            }
+        }
+    }
+
+    Ok(tasks)
+}
+
+fn read_description(day_dir: &Path) -> anyhow::Result<(String, Vec<String>)> {
+    let markdown_file = day_dir.join("description.md");
+    if markdown_file.exists() {
+        return Ok((strip_answers(&read_file(&markdown_file)?), vec![]));
+    }
+
+    let html_file = day_dir.join("puzzle.html");
+    let page = parse_puzzle_page(&read_file(&html_file)?)
+        .with_context(|| format!("cannot parse {}", html_file.display()))?;
+    Ok((page.description(), page.answers))
+}
+
+fn numbered_subdirs(dir: &Path, prefix: &str) -> anyhow::Result<Vec<(i64, PathBuf)>> {
+    let mut subdirs = vec![];
+
+    for entry in fs::read_dir(dir).with_context(|| format!("cannot read {}", dir.display()))? {
+        let path = entry?.path();
+        let number = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(prefix))
+            .and_then(|number| number.parse::<i64>().ok());
+
+        if let (Some(number), true) = (number, path.is_dir()) {
+            subdirs.push((number, path));
+        }
+    }
+
+    subdirs.sort();
+    Ok(subdirs)
+}
+
+fn read_file(path: &Path) -> anyhow::Result<String> {
+    fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))
+}
+
+fn read_answer(path: &Path) -> anyhow::Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(read_file(path)?.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use uuid::Uuid;
+
+    use super::*;
+
+    const DESCRIPTION: &str = "--- Day 1: Trebuchet ---\n\nSum the numbers.\n\nYour puzzle answer was 142.\n\n--- Part Two ---\n\nSpelled out too.";
+
+    fn temp_dir() -> PathBuf {
+        let dir = env::temp_dir().join(format!("import-test-{}", Uuid::now_v7()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: PathBuf, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn numbered_subdirs_are_sorted_by_number() {
+        let root = temp_dir();
+        for name in ["day10", "day2", "day1", "notes", "daytwo"] {
+            fs::create_dir(root.join(name)).unwrap();
+        }
+        write(root.join("day3"), "not a directory");
+
+        let subdirs = numbered_subdirs(&root, "day").unwrap();
+
+        assert_eq!(
+            subdirs,
+            vec![
+                (1, root.join("day1")),
+                (2, root.join("day2")),
+                (10, root.join("day10")),
+            ]
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn puzzle_parts_split_the_description() {
+        let root = temp_dir();
+        let day = root.join("2023").join("day1");
+        write(day.join("description.md"), DESCRIPTION);
+        write(day.join("input.txt"), "1abc2\n");
+        write(day.join("answer1.txt"), "142\n");
+        write(day.join("answer2.txt"), "281\n");
+
+        let tasks = read_puzzle_dir(&root).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        let (one, two) = (&tasks[0], &tasks[1]);
+        assert_eq!((one.year, one.day, one.part), (2023, 1, 1));
+        assert_eq!(one.dto.name, "Advent of code 2023, Day 1, Part One");
+        assert_eq!(one.dto.solution, "142");
+        assert_eq!(
+            one.dto.task_type,
+            TaskTypeDto::AdventOfCodePartOne {
+                description: "--- Day 1: Trebuchet ---\n\nSum the numbers.".to_string(),
+                input: "1abc2\n".to_string(),
+                examples: vec![],
+            }
+        );
+        assert_eq!((two.year, two.day, two.part), (2023, 1, 2));
+        assert_eq!(two.dto.name, "Advent of code 2023, Day 1, Part Two");
+        assert_eq!(two.dto.solution, "281");
+        assert_eq!(
+            two.dto.task_type,
+            TaskTypeDto::AdventOfCodePartTwo {
+                description: "--- Day 1: Trebuchet ---\n\nSum the numbers.\n\n--- Part Two ---\n\nSpelled out too.".to_string(),
+                input: "1abc2\n".to_string(),
+                examples: vec![],
+            }
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn parts_without_an_answer_are_skipped() {
+        let root = temp_dir();
+        let answered = root.join("2023").join("day2");
+        write(answered.join("description.md"), "Count the cubes.");
+        write(answered.join("input.txt"), "Game 1\n");
+        write(answered.join("answer1.txt"), "8");
+        let unanswered = root.join("2022").join("day1");
+        write(unanswered.join("description.md"), "Count the calories.");
+        write(unanswered.join("input.txt"), "1000\n");
+
+        let tasks = read_puzzle_dir(&root).unwrap();
+
+        let parts: Vec<_> = tasks
+            .iter()
+            .map(|task| (task.year, task.day, task.part))
+            .collect();
+        assert_eq!(parts, vec![(2023, 2, 1)]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn missing_input_names_the_file() {
+        let root = temp_dir();
+        let day = root.join("2023").join("day1");
+        write(day.join("description.md"), DESCRIPTION);
+
+        let error = read_puzzle_dir(&root).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            format!("cannot read {}", day.join("input.txt").display())
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use axum::{extract::Path, Extension, Json};
+use dto::LeaderboardEntryDto;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    agent::AgentInDb, completion::CompletionInDb, error::AppError, llm_usage::LlmUsageInDb,
+    task::TaskInDb,
+};
+
+/// The agents that completed a task with benchmarked code, fastest run time first. Ties are
+/// broken by the time it took to solve the task.
+pub async fn read_leaderboard(
+    Extension(pool): Extension<SqlitePool>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<Vec<LeaderboardEntryDto>>, AppError> {
+    TaskInDb::read_active(&pool, task_id).await?;
+    let agents: HashMap<Uuid, AgentInDb> = AgentInDb::read_all(&pool)
+        .await?
+        .into_iter()
+        .map(|model| (model.id, model))
+        .collect();
+    let mut llm_tokens: HashMap<Uuid, i64> = HashMap::new();
+    for usage in LlmUsageInDb::read_all_by_task(&pool, task_id).await? {
+        *llm_tokens.entry(usage.agent_id).or_default() += usage.tokens();
+    }
+
+    let mut completions: Vec<(i64, CompletionInDb)> =
+        CompletionInDb::read_all_by_task(&pool, task_id)
+            .await?
+            .into_iter()
+            .filter(|completion| completion.completion_time.is_some())
+            .filter_map(|completion| Some((completion.best_run_time_in_ms?, completion)))
+            .collect();
+    completions.sort_by_key(|(run_time_in_ms, completion)| {
+        (
+            *run_time_in_ms,
+            completion.best_time_in_ms.unwrap_or(i64::MAX),
+        )
+    });
+
+    let dtos = completions
+        .into_iter()
+        .enumerate()
+        .map(
+            |(index, (run_time_in_ms, completion))| LeaderboardEntryDto {
+                rank: index + 1,
+                agent_id: completion.agent_id,
+                agent_name: agents
+                    .get(&completion.agent_id)
+                    .map(|agent| agent.name.clone())
+                    .unwrap_or_default(),
+                run_time_in_ms,
+                time_in_ms: completion.best_time_in_ms,
+                llm_tokens: llm_tokens.get(&completion.agent_id).copied(),
+            },
+        )
+        .collect();
+
+    Ok(Json(dtos))
+}
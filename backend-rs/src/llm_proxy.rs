@@ -0,0 +1,407 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::Path as FsPath,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use anyhow::Context;
+use axum::{
+    body::Bytes,
+    extract::{Path, RawQuery},
+    http::{
+        header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    response::{IntoResponse, Response},
+    Extension,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tokio::sync::Mutex as AsyncMutex;
+use uuid::Uuid;
+
+use crate::{
+    agent::AgentInDb,
+    error::{AppError, DtoValidationError},
+    llm_usage::LlmUsageInDb,
+    task::TaskInDb,
+};
+
+pub const LLM_BODY_LIMIT: usize = 16 * 1024 * 1024;
+
+/// Header with the task a request to the proxy is made for, so the usage is counted per task.
+const TASK_ID_HEADER: &str = "x-task-id";
+
+/// Rough size of a token, for responses that do not report their tokens.
+const BYTES_PER_TOKEN: usize = 4;
+
+/// An entry of the JSON file in `LLM_PROVIDERS_FILE`, e.g.
+/// `"openai": { "url": "https://api.openai.com/v1", "api_key_env": "OPENAI_API_KEY" }`.
+#[derive(Debug, Deserialize)]
+struct ProviderConfig {
+    url: String,
+    /// Environment variable of the backend with the API key of the provider.
+    api_key_env: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Upstream {
+    url: String,
+    api_key: Option<String>,
+}
+
+/// Forwards the requests of agents to the configured LLM providers and meters their tokens and
+/// latency. Without providers every request is rejected.
+#[derive(Debug, Clone)]
+pub struct LlmProxy {
+    providers: Arc<HashMap<String, Upstream>>,
+    client: reqwest::Client,
+    /// Tokens each agent may use in total, unlimited if `None`.
+    pub token_budget: Option<i64>,
+    /// Requests of an agent with a budget are forwarded one at a time, so that parallel requests
+    /// cannot all pass the budget check before the first one is counted.
+    agent_locks: Arc<Mutex<HashMap<Uuid, Arc<AsyncMutex<()>>>>>,
+}
+
+impl LlmProxy {
+    pub fn new(providers: HashMap<String, Upstream>, token_budget: Option<i64>) -> Self {
+        Self {
+            providers: Arc::new(providers),
+            client: reqwest::Client::new(),
+            token_budget,
+            agent_locks: Arc::default(),
+        }
+    }
+
+    fn agent_lock(&self, agent_id: Uuid) -> Arc<AsyncMutex<()>> {
+        let mut locks = self.agent_locks.lock().unwrap();
+        locks.entry(agent_id).or_default().clone()
+    }
+}
+
+/// Reads the providers by name, with the API keys from the environment.
+pub fn read_providers(path: &FsPath) -> anyhow::Result<HashMap<String, Upstream>> {
+    let json = fs::read_to_string(path)
+        .with_context(|| format!("failed to read LLM providers {}", path.display()))?;
+    let configs: HashMap<String, ProviderConfig> = serde_json::from_str(&json)?;
+
+    let mut providers = HashMap::new();
+    for (name, config) in configs {
+        let api_key = match &config.api_key_env {
+            Some(key) => Some(env::var(key).with_context(|| {
+                format!("environment variable {key} of LLM provider {name} is not set")
+            })?),
+            None => None,
+        };
+        let upstream = Upstream {
+            url: config.url.trim_end_matches('/').to_string(),
+            api_key,
+        };
+        providers.insert(name, upstream);
+    }
+    Ok(providers)
+}
+
+/// Forwards `<path>` to the provider, e.g. `/agent/<id>/llm/ollama/api/chat` to
+/// `<url of ollama>/api/chat`. The agent authenticates with its token as bearer token, so it can
+/// be used as API key of an LLM client. Requests of agents that used up their budget are rejected
+/// before they are forwarded.
+pub async fn proxy_llm(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(proxy): Extension<LlmProxy>,
+    Path((agent_id, provider, path)): Path<(Uuid, String, String)>,
+    RawQuery(query): RawQuery,
+    method: Method,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, AppError> {
+    let agent = AgentInDb::read(&pool, agent_id).await?;
+    if bearer_token(&headers) != Some(agent.token) {
+        return Err(AppError::Unauthorized);
+    }
+    let task_id = match headers.get(TASK_ID_HEADER) {
+        Some(value) => {
+            let task_id = value
+                .to_str()
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| DtoValidationError("invalid X-Task-Id header".into()))?;
+            if !TaskInDb::exists_active(&pool, task_id).await? {
+                return Err(AppError::ValidationError(DtoValidationError(format!(
+                    "no task {task_id}"
+                ))));
+            }
+            Some(task_id)
+        }
+        None => None,
+    };
+    let Some(upstream) = proxy.providers.get(&provider) else {
+        return Err(AppError::ValidationError(DtoValidationError(format!(
+            "no LLM provider {provider}"
+        ))));
+    };
+
+    let agent_lock = proxy.agent_lock(agent_id);
+    let _budget_guard = match proxy.token_budget {
+        Some(token_budget) => {
+            let guard = agent_lock.lock().await;
+            if LlmUsageInDb::tokens_by_agent(&pool, agent_id).await? >= token_budget {
+                return Err(AppError::BudgetExceeded);
+            }
+            Some(guard)
+        }
+        None => None,
+    };
+
+    let mut url = format!("{}/{path}", upstream.url);
+    if let Some(query) = query {
+        url = format!("{url}?{query}");
+    }
+    let body = with_stream_usage(&path, body);
+    let model = serde_json::from_slice::<Value>(&body)
+        .ok()
+        .and_then(|body| Some(body.get("model")?.as_str()?.to_string()));
+    let request_bytes = body.len();
+    let mut request = proxy.client.request(method, url).body(body);
+    for header in [CONTENT_TYPE, ACCEPT] {
+        if let Some(value) = headers.get(&header) {
+            request = request.header(header, value);
+        }
+    }
+    if let Some(api_key) = &upstream.api_key {
+        request = request.bearer_auth(api_key);
+    }
+
+    let start = Instant::now();
+    let result = forward(request).await;
+    let mut usage = LlmUsageInDb {
+        id: Uuid::now_v7(),
+        agent_id,
+        task_id,
+        provider,
+        model,
+        prompt_tokens: 0,
+        completion_tokens: 0,
+        latency_ms: start.elapsed().as_millis() as i64,
+        status: StatusCode::BAD_GATEWAY.as_u16().into(),
+        request_time: Utc::now(),
+    };
+
+    let response = match result {
+        Ok((status, content_type, body)) => {
+            let tokens = match count_tokens(&body) {
+                Some(tokens) => Some(tokens),
+                None if status.is_success() => {
+                    Some((estimate_tokens(request_bytes), estimate_tokens(body.len())))
+                }
+                None => None,
+            };
+            if let Some((prompt_tokens, completion_tokens)) = tokens {
+                usage.prompt_tokens = prompt_tokens;
+                usage.completion_tokens = completion_tokens;
+            }
+            usage.status = status.as_u16().into();
+            let mut response = (status, body).into_response();
+            if let Some(content_type) = content_type {
+                response.headers_mut().insert(CONTENT_TYPE, content_type);
+            }
+            response
+        }
+        Err(error) => (
+            StatusCode::BAD_GATEWAY,
+            format!("LLM provider {} failed: {error}", usage.provider),
+        )
+            .into_response(),
+    };
+    usage.create(&pool).await?;
+    Ok(response)
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<Uuid> {
+    let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+    value.strip_prefix("Bearer ")?.trim().parse().ok()
+}
+
+/// Asks for the usage in the last chunk of streamed requests to the completions of the OpenAI API,
+/// which otherwise do not report it. Ollama reports it anyway.
+fn with_stream_usage(path: &str, body: Bytes) -> Bytes {
+    if !path.ends_with("completions") {
+        return body;
+    }
+    let Ok(Value::Object(mut request)) = serde_json::from_slice::<Value>(&body) else {
+        return body;
+    };
+    if request.get("stream") != Some(&Value::Bool(true)) {
+        return body;
+    }
+    let options = request
+        .entry("stream_options")
+        .or_insert_with(|| Value::Object(Default::default()));
+    let Value::Object(options) = options else {
+        return body;
+    };
+    options.insert("include_usage".into(), Value::Bool(true));
+    serde_json::to_vec(&request).map_or(body, Bytes::from)
+}
+
+/// Sends the request and reads the whole response, so streamed responses are passed on at once.
+async fn forward(
+    request: reqwest::RequestBuilder,
+) -> reqwest::Result<(StatusCode, Option<HeaderValue>, Bytes)> {
+    let response = request.send().await?;
+    let status = response.status();
+    let content_type = response.headers().get(CONTENT_TYPE).cloned();
+    Ok((status, content_type, response.bytes().await?))
+}
+
+/// The prompt and completion tokens the response reports: `usage` of the OpenAI API or
+/// `prompt_eval_count` and `eval_count` of the Ollama API. Streamed responses (lines of JSON or
+/// server-sent events) report them in their last chunk.
+fn count_tokens(body: &[u8]) -> Option<(i64, i64)> {
+    if let Ok(value) = serde_json::from_slice::<Value>(body) {
+        return token_counts(&value);
+    }
+    String::from_utf8_lossy(body)
+        .lines()
+        .map(|line| line.strip_prefix("data:").unwrap_or(line).trim())
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter_map(|value| token_counts(&value))
+        .last()
+}
+
+/// The tokens of a body of that size, for successful responses that do not report their tokens.
+fn estimate_tokens(bytes: usize) -> i64 {
+    bytes.div_ceil(BYTES_PER_TOKEN) as i64
+}
+
+fn token_counts(value: &Value) -> Option<(i64, i64)> {
+    let count = |value: &Value, key: &str| value.get(key).and_then(Value::as_i64);
+    match value.get("usage").filter(|usage| usage.is_object()) {
+        Some(usage) => Some((
+            count(usage, "prompt_tokens").unwrap_or_default(),
+            count(usage, "completion_tokens").unwrap_or_default(),
+        )),
+        None => {
+            let prompt_tokens = count(value, "prompt_eval_count");
+            let completion_tokens = count(value, "eval_count");
+            if prompt_tokens.is_none() && completion_tokens.is_none() {
+                return None;
+            }
+            Some((
+                prompt_tokens.unwrap_or_default(),
+                completion_tokens.unwrap_or_default(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn counts_tokens_of_openai_response() {
+        let body = json!({
+            "choices": [{ "message": { "role": "assistant", "content": "42" } }],
+            "usage": { "prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15 },
+        });
+        assert_eq!(count_tokens(body.to_string().as_bytes()), Some((12, 3)));
+    }
+
+    #[test]
+    fn counts_tokens_of_ollama_response() {
+        let body =
+            json!({ "response": "42", "done": true, "prompt_eval_count": 20, "eval_count": 5 });
+        assert_eq!(count_tokens(body.to_string().as_bytes()), Some((20, 5)));
+    }
+
+    #[test]
+    fn counts_tokens_of_ollama_stream() {
+        let body = concat!(
+            r#"{"message":{"content":"4"},"done":false}"#,
+            "\n",
+            r#"{"message":{"content":"2"},"done":false}"#,
+            "\n",
+            r#"{"message":{"content":""},"done":true,"prompt_eval_count":20,"eval_count":2}"#,
+            "\n",
+        );
+        assert_eq!(count_tokens(body.as_bytes()), Some((20, 2)));
+    }
+
+    #[test]
+    fn counts_tokens_of_server_sent_events() {
+        let body = concat!(
+            r#"data: {"choices":[{"delta":{"content":"4"}}],"usage":null}"#,
+            "\n\n",
+            r#"data: {"choices":[{"delta":{"content":"2"}}],"usage":null}"#,
+            "\n\n",
+            r#"data: {"choices":[],"usage":{"prompt_tokens":9,"completion_tokens":2}}"#,
+            "\n\n",
+            "data: [DONE]\n\n",
+        );
+        assert_eq!(count_tokens(body.as_bytes()), Some((9, 2)));
+    }
+
+    #[test]
+    fn counts_no_tokens_without_usage() {
+        let stream = concat!(
+            r#"data: {"choices":[{"delta":{"content":"42"}}]}"#,
+            "\n\ndata: [DONE]\n\n"
+        );
+        assert_eq!(count_tokens(stream.as_bytes()), None);
+        assert_eq!(count_tokens(br#"{"error":"model not found"}"#), None);
+        assert_eq!(count_tokens(b"Bad Gateway"), None);
+    }
+
+    #[test]
+    fn asks_openai_streams_for_usage() {
+        let body = json!({ "model": "m", "stream": true, "stream_options": { "other": 1 } });
+        let body = with_stream_usage("v1/chat/completions", body.to_string().into());
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({ "model": "m", "stream": true, "stream_options": { "other": 1, "include_usage": true } })
+        );
+
+        let body = json!({ "model": "m", "stream": true });
+        let body = with_stream_usage("v1/completions", body.to_string().into());
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap()["stream_options"],
+            json!({ "include_usage": true })
+        );
+    }
+
+    #[test]
+    fn leaves_other_requests_alone() {
+        for (path, body) in [
+            ("v1/chat/completions", json!({ "model": "m" }).to_string()),
+            (
+                "v1/chat/completions",
+                json!({ "model": "m", "stream": false }).to_string(),
+            ),
+            (
+                "api/chat",
+                json!({ "model": "m", "stream": true }).to_string(),
+            ),
+            ("v1/chat/completions", "not json".to_string()),
+        ] {
+            assert_eq!(
+                with_stream_usage(path, body.clone().into()),
+                body.as_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn estimates_tokens_from_size() {
+        assert_eq!(estimate_tokens(0), 0);
+        assert_eq!(estimate_tokens(1), 1);
+        assert_eq!(estimate_tokens(8), 2);
+        assert_eq!(estimate_tokens(9), 3);
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{
+    extract::{Path, Query},
+    Extension, Json,
+};
+use chrono::{DateTime, Utc};
+use dto::{LlmTaskUsageDto, LlmUsageCountsDto, LlmUsageDto};
+use sqlx::{FromRow, SqlitePool};
+use uuid::Uuid;
+
+use crate::{agent::AgentInDb, error::AppError, llm_proxy::LlmProxy, task::AgentToken};
+
+/// A request an agent sent to an LLM provider through the proxy.
+#[derive(FromRow, Debug, PartialEq)]
+pub struct LlmUsageInDb {
+    pub id: Uuid,
+    pub agent_id: Uuid,
+    pub task_id: Option<Uuid>,
+    pub provider: String,
+    pub model: Option<String>,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    pub latency_ms: i64,
+    /// HTTP status of the upstream response, 502 if the provider could not be reached.
+    pub status: i64,
+    pub request_time: DateTime<Utc>,
+}
+
+pub async fn read_llm_usage(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(proxy): Extension<LlmProxy>,
+    Path(agent_id): Path<Uuid>,
+    token: Query<AgentToken>,
+) -> Result<Json<LlmUsageDto>, AppError> {
+    let agent = AgentInDb::read(&pool, agent_id).await?;
+    if agent.token != token.token {
+        return Err(AppError::Unauthorized);
+    }
+
+    let usages = LlmUsageInDb::read_all_by_agent(&pool, agent_id).await?;
+    Ok(Json(usage_dto(agent_id, proxy.token_budget, &usages)))
+}
+
+/// The usage of every agent, also the ones that did not use the proxy.
+pub async fn read_all_llm_usage(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(proxy): Extension<LlmProxy>,
+) -> Result<Json<Vec<LlmUsageDto>>, AppError> {
+    let mut usages_by_agent: HashMap<Uuid, Vec<LlmUsageInDb>> = HashMap::new();
+    for usage in LlmUsageInDb::read_all(&pool).await? {
+        usages_by_agent
+            .entry(usage.agent_id)
+            .or_default()
+            .push(usage);
+    }
+
+    let dtos = AgentInDb::read_all(&pool)
+        .await?
+        .into_iter()
+        .map(|agent| {
+            let usages = usages_by_agent.remove(&agent.id).unwrap_or_default();
+            usage_dto(agent.id, proxy.token_budget, &usages)
+        })
+        .collect();
+    Ok(Json(dtos))
+}
+
+fn usage_dto(agent_id: Uuid, token_budget: Option<i64>, usages: &[LlmUsageInDb]) -> LlmUsageDto {
+    let mut total = LlmUsageCountsDto::default();
+    let mut tasks: BTreeMap<Option<Uuid>, LlmUsageCountsDto> = BTreeMap::new();
+    for usage in usages {
+        add(&mut total, usage);
+        add(tasks.entry(usage.task_id).or_default(), usage);
+    }
+
+    LlmUsageDto {
+        agent_id,
+        token_budget,
+        total,
+        tasks: tasks
+            .into_iter()
+            .map(|(task_id, usage)| LlmTaskUsageDto { task_id, usage })
+            .collect(),
+    }
+}
+
+fn add(counts: &mut LlmUsageCountsDto, usage: &LlmUsageInDb) {
+    counts.requests += 1;
+    counts.prompt_tokens += usage.prompt_tokens;
+    counts.completion_tokens += usage.completion_tokens;
+    counts.latency_ms += usage.latency_ms;
+}
+
+impl LlmUsageInDb {
+    pub fn tokens(&self) -> i64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    pub async fn create(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(
+            r#"INSERT INTO llm_usage (id, agent_id, task_id, provider, model, prompt_tokens, completion_tokens, latency_ms, status, request_time) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
+            self.id,
+            self.agent_id,
+            self.task_id,
+            self.provider,
+            self.model,
+            self.prompt_tokens,
+            self.completion_tokens,
+            self.latency_ms,
+            self.status,
+            self.request_time,
+        )
+        .execute(conn.as_mut())
+        .await?;
+
+        Ok(())
+    }
+
+    /// The prompt and completion tokens of all requests of the agent.
+    pub async fn tokens_by_agent(pool: &SqlitePool, agent_id: Uuid) -> anyhow::Result<i64> {
+        let mut conn = pool.acquire().await?;
+        let tokens = sqlx::query_scalar!(
+            r#"SELECT COALESCE(SUM(prompt_tokens + completion_tokens), 0) as "tokens!: i64" FROM llm_usage WHERE agent_id = ?;"#,
+            agent_id,
+        )
+        .fetch_one(conn.as_mut())
+        .await?;
+        Ok(tokens)
+    }
+
+    pub async fn read_all(pool: &SqlitePool) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let models = sqlx::query_as!(
+            Self,
+            r#"SELECT id as "id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", task_id as "task_id: uuid::Uuid", provider, model, prompt_tokens, completion_tokens, latency_ms, status, request_time as "request_time: chrono::DateTime<Utc>" FROM llm_usage ORDER BY request_time;"#,
+        )
+        .fetch_all(conn.as_mut())
+        .await?;
+        Ok(models)
+    }
+
+    pub async fn read_all_by_agent(pool: &SqlitePool, agent_id: Uuid) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let models = sqlx::query_as!(
+            Self,
+            r#"SELECT id as "id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", task_id as "task_id: uuid::Uuid", provider, model, prompt_tokens, completion_tokens, latency_ms, status, request_time as "request_time: chrono::DateTime<Utc>" FROM llm_usage WHERE agent_id = ? ORDER BY request_time;"#,
+            agent_id,
+        )
+        .fetch_all(conn.as_mut())
+        .await?;
+        Ok(models)
+    }
+
+    pub async fn read_all_by_task(pool: &SqlitePool, task_id: Uuid) -> anyhow::Result<Vec<Self>> {
+        let mut conn = pool.acquire().await?;
+        let models = sqlx::query_as!(
+            Self,
+            r#"SELECT id as "id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", task_id as "task_id: uuid::Uuid", provider, model, prompt_tokens, completion_tokens, latency_ms, status, request_time as "request_time: chrono::DateTime<Utc>" FROM llm_usage WHERE task_id = ? ORDER BY request_time;"#,
+            task_id,
+        )
+        .fetch_all(conn.as_mut())
+        .await?;
+        Ok(models)
+    }
+}
@@ -1,16 +1,31 @@
 mod agent;
+mod aoc_html;
 mod auth;
+mod benchmark;
+mod bundle;
 mod check;
+mod code_runner;
+mod code_submission;
 mod completion;
 mod error;
+mod example_check;
+mod examples;
 mod health_check;
+mod import;
+mod leaderboard;
+mod llm_proxy;
+mod llm_usage;
+mod rate_limit;
+mod regrade;
 mod route;
+mod submission;
 mod task;
+mod task_input;
 mod traits;
 
-use std::env;
+use std::{collections::HashMap, env, path::Path, str::FromStr, time::Duration};
 
-use anyhow::{Ok, Result};
+use anyhow::{bail, Context, Ok, Result};
 use axum::{
     http::{
         header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE},
@@ -18,13 +33,32 @@ use axum::{
     },
     Extension,
 };
+use benchmark::Benchmarker;
+use code_runner::CodeRunner;
+use dto::TaskTypeDto;
+use example_check::ExampleCheckRateLimiter;
+use examples::{add_examples, suggest_examples};
+use import::{import_tasks, read_puzzle_dir, validate_all};
+use llm_proxy::{read_providers, LlmProxy};
+use rate_limit::RateLimiter;
 use route::create_router;
+use sandbox::{Limits, Registry};
+use solver::Solver;
 use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use task::TaskInDb;
 use tower_http::cors::CorsLayer;
+use uuid::Uuid;
 
 const DEFAULT_USER_TOKEN: &str = "SwexCamp2024!";
 const DEFAULT_ADMIN_TOKEN: &str = "SwexCamp2024Admin!";
 const DEFAULT_DATABASE_URL: &str = "sqlite://sqlite.db";
+const DEFAULT_EXAMPLE_CHECKS_PER_MINUTE: usize = 30;
+const DEFAULT_CODE_COMPILE_TIMEOUT_SECS: u64 = 60;
+const DEFAULT_CODE_CPU_TIME_SECS: u64 = 10;
+const DEFAULT_CODE_WALL_TIME_SECS: u64 = 20;
+const DEFAULT_CODE_MEMORY_LIMIT_MB: u64 = 1024;
+const DEFAULT_CODE_PARALLEL_RUNS: usize = 2;
+const DEFAULT_BENCHMARK_RUNS: usize = 5;
 const ADDRESS: &str = "0.0.0.0:8000";
 
 #[tokio::main]
@@ -35,11 +69,73 @@ async fn main() -> Result<()> {
     let admin_token = env::var("ADMIN_TOKEN").unwrap_or(DEFAULT_ADMIN_TOKEN.into());
     let database_url = env::var("DATABASE_URL").unwrap_or(DEFAULT_DATABASE_URL.into());
 
-    let router = create_router(&user_token, &admin_token);
-
     let db = init_db(database_url.trim()).await?;
 
-    let app = router.layer(create_cors_layer()?).layer(Extension(db));
+    let args: Vec<String> = env::args().collect();
+    match args.as_slice() {
+        [_] => serve(db, &user_token, &admin_token).await,
+        [_, command, path] if command == "import" => import(&db, path).await,
+        [_, command, task_id] if command == "examples" => {
+            print_examples(&db, task_id.parse()?).await
+        }
+        [_, command, task_id, accept, indices @ ..]
+            if command == "examples" && accept == "accept" =>
+        {
+            let indices = indices
+                .iter()
+                .map(|index| index.parse())
+                .collect::<Result<Vec<usize>, _>>()?;
+            accept_examples(&db, task_id.parse()?, &indices).await
+        }
+        _ => bail!(
+            "usage: backend-rs [import <puzzle dir> | examples <task id> [accept <index>...]]"
+        ),
+    }
+}
+
+async fn serve(db: Pool<Sqlite>, user_token: &str, admin_token: &str) -> Result<()> {
+    let router = create_router(user_token, admin_token);
+
+    let example_checks_per_minute = env_or(
+        "EXAMPLE_CHECKS_PER_MINUTE",
+        DEFAULT_EXAMPLE_CHECKS_PER_MINUTE,
+    )?;
+    let example_check_rate_limiter = ExampleCheckRateLimiter(RateLimiter::new(
+        example_checks_per_minute,
+        Duration::from_secs(60),
+    ));
+
+    let compile_timeout = Duration::from_secs(env_or(
+        "CODE_COMPILE_TIMEOUT_SECS",
+        DEFAULT_CODE_COMPILE_TIMEOUT_SECS,
+    )?);
+    let limits = Limits {
+        cpu_time: Duration::from_secs(env_or("CODE_CPU_TIME_SECS", DEFAULT_CODE_CPU_TIME_SECS)?),
+        wall_time: Duration::from_secs(env_or("CODE_WALL_TIME_SECS", DEFAULT_CODE_WALL_TIME_SECS)?),
+        memory_bytes: env_or("CODE_MEMORY_LIMIT_MB", DEFAULT_CODE_MEMORY_LIMIT_MB)? * 1024 * 1024,
+        ..Default::default()
+    };
+    let code_runner = CodeRunner::new(
+        compile_timeout,
+        confined_runtime_registry(&limits)?,
+        env_count("CODE_PARALLEL_RUNS", DEFAULT_CODE_PARALLEL_RUNS)?,
+    );
+
+    let solver_dir = env::var_os("SOLVER_DIR").map_or_else(Solver::default_dir, Into::into);
+    let benchmarker = Benchmarker::new(
+        Solver::new(solver_dir),
+        compile_timeout,
+        env_count("BENCHMARK_RUNS", DEFAULT_BENCHMARK_RUNS)?,
+        limits,
+    );
+
+    let app = router
+        .layer(create_cors_layer()?)
+        .layer(Extension(db))
+        .layer(Extension(example_check_rate_limiter))
+        .layer(Extension(code_runner))
+        .layer(Extension(benchmarker))
+        .layer(Extension(llm_proxy()?));
 
     println!("🚀 Server started successfully");
 
@@ -48,6 +144,111 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// The runtimes of `runtime_registry`, or none if the sandbox cannot confine builds and runs on
+/// this system: submitted code must not be able to read the files of the backend.
+fn confined_runtime_registry(limits: &Limits) -> Result<Registry> {
+    if let Err(error) = sandbox::check_confinement() {
+        eprintln!("code submissions are disabled: {error}");
+        return Ok(Registry { runtimes: vec![] });
+    }
+    runtime_registry(limits)
+}
+
+/// The runtimes of the JSON file in `RUNTIMES_FILE` or the built-in ones (with the given limits),
+/// if their toolchains are installed.
+fn runtime_registry(limits: &Limits) -> Result<Registry> {
+    let registry = match env::var_os("RUNTIMES_FILE") {
+        Some(path) => Registry::from_file(Path::new(&path))?,
+        None => Registry::builtin(limits),
+    };
+    Ok(registry.available())
+}
+
+/// The LLM proxy with the providers of the JSON file in `LLM_PROVIDERS_FILE` (without providers
+/// if it is not set) and the per-agent token budget in `LLM_TOKEN_BUDGET`.
+fn llm_proxy() -> Result<LlmProxy> {
+    let providers = match env::var_os("LLM_PROVIDERS_FILE") {
+        Some(path) => read_providers(Path::new(&path))?,
+        None => HashMap::new(),
+    };
+    let token_budget = env::var("LLM_TOKEN_BUDGET")
+        .ok()
+        .map(|budget| budget.trim().parse())
+        .transpose()
+        .context("invalid LLM_TOKEN_BUDGET")?;
+    Ok(LlmProxy::new(providers, token_budget))
+}
+
+/// The value of the environment variable, the default if it is not set. Invalid values are an
+/// error, so a typo does not go unnoticed.
+fn env_or<T>(key: &str, default: T) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let value = env::var(key)
+        .ok()
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid {key}: {value}"))
+        })
+        .transpose()?;
+    Ok(value.unwrap_or(default))
+}
+
+/// Like `env_or`, for counts that must be at least 1 (e.g. the permits of a semaphore).
+fn env_count(key: &str, default: usize) -> Result<usize> {
+    let count = env_or(key, default)?;
+    if count == 0 {
+        bail!("{key} must be at least 1");
+    }
+    Ok(count)
+}
+
+async fn import(db: &Pool<Sqlite>, path: &str) -> Result<()> {
+    let tasks = read_puzzle_dir(Path::new(path))?;
+    validate_all(&tasks)?;
+    let report = import_tasks(db, tasks).await?;
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+async fn print_examples(db: &Pool<Sqlite>, task_id: Uuid) -> Result<()> {
+    let task = TaskInDb::read(db, task_id).await?;
+    let task_type: TaskTypeDto = serde_json::from_str(&task.task_json)?;
+
+    for (index, example) in suggest_examples(task_type.description()).iter().enumerate() {
+        println!(
+            "[{index}] part {}, expected output: {}",
+            example.part, example.expected_output
+        );
+        println!("{}", example.input);
+    }
+    Ok(())
+}
+
+async fn accept_examples(db: &Pool<Sqlite>, task_id: Uuid, indices: &[usize]) -> Result<()> {
+    let task = TaskInDb::read(db, task_id).await?;
+    let task_type: TaskTypeDto = serde_json::from_str(&task.task_json)?;
+    let suggestions = suggest_examples(task_type.description());
+
+    let mut accepted = vec![];
+    for &index in indices {
+        match suggestions.get(index) {
+            Some(example) => accepted.push(example.clone()),
+            None => bail!("no suggested example with index {index}"),
+        }
+    }
+
+    let examples = add_examples(db, task_id, accepted).await?;
+
+    println!("{}", serde_json::to_string_pretty(&examples)?);
+    Ok(())
+}
+
 async fn init_db(database_url: &str) -> Result<Pool<Sqlite>> {
     let pool = SqlitePoolOptions::new().connect(database_url).await?;
     sqlx::migrate!("./migrations").run(&pool).await?;
@@ -0,0 +1,91 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+/// Sliding window limiter: at most `max_requests` per `window` and key (e.g. agent ID).
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    max_requests: usize,
+    window: Duration,
+    requests: Arc<Mutex<HashMap<Uuid, VecDeque<Instant>>>>,
+}
+
+impl RateLimiter {
+    pub fn new(max_requests: usize, window: Duration) -> Self {
+        Self {
+            max_requests,
+            window,
+            requests: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Records a request for the key and returns whether it is within the limit.
+    pub fn try_acquire(&self, key: Uuid) -> bool {
+        self.try_acquire_at(key, Instant::now())
+    }
+
+    fn try_acquire_at(&self, key: Uuid, now: Instant) -> bool {
+        let mut requests = self.requests.lock().unwrap();
+        let timestamps = requests.entry(key).or_default();
+
+        while timestamps
+            .front()
+            .is_some_and(|timestamp| now.duration_since(*timestamp) >= self.window)
+        {
+            timestamps.pop_front();
+        }
+
+        if timestamps.len() >= self.max_requests {
+            return false;
+        }
+        timestamps.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn rejects_requests_over_the_limit() {
+        let limiter = RateLimiter::new(2, WINDOW);
+        let (key, start) = (Uuid::now_v7(), Instant::now());
+
+        assert!(limiter.try_acquire_at(key, start));
+        assert!(limiter.try_acquire_at(key, start + Duration::from_secs(1)));
+        assert!(!limiter.try_acquire_at(key, start + Duration::from_secs(2)));
+        // Rejected requests don't count.
+        assert!(!limiter.try_acquire_at(key, start + Duration::from_secs(3)));
+    }
+
+    #[test]
+    fn requests_leave_the_window() {
+        let limiter = RateLimiter::new(2, WINDOW);
+        let (key, start) = (Uuid::now_v7(), Instant::now());
+        assert!(limiter.try_acquire_at(key, start));
+        assert!(limiter.try_acquire_at(key, start + Duration::from_secs(30)));
+
+        assert!(!limiter.try_acquire_at(key, start + WINDOW - Duration::from_millis(1)));
+        assert!(limiter.try_acquire_at(key, start + WINDOW));
+        assert!(!limiter.try_acquire_at(key, start + WINDOW + Duration::from_secs(1)));
+        assert!(limiter.try_acquire_at(key, start + Duration::from_secs(90)));
+    }
+
+    #[test]
+    fn counts_every_key_separately() {
+        let limiter = RateLimiter::new(1, WINDOW);
+        let (first, second, now) = (Uuid::now_v7(), Uuid::now_v7(), Instant::now());
+
+        assert!(limiter.try_acquire_at(first, now));
+        assert!(!limiter.try_acquire_at(first, now));
+        assert!(limiter.try_acquire_at(second, now));
+        assert!(!limiter.try_acquire_at(second, now));
+    }
+}
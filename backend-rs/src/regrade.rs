@@ -0,0 +1,184 @@
+use axum::{extract::Path, Extension, Json};
+use chrono::{DateTime, Utc};
+use dto::{RegradeAgentDto, RegradeChangeDto, RegradeReportDto};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    check::is_correct, completion::CompletionInDb, error::AppError, submission::SubmissionInDb,
+    task::TaskInDb,
+};
+
+/// Re-runs the comparison over all stored submissions of a task against its current solution.
+/// The earliest matching submission completes the task (timed from the original submission),
+/// agents without a matching submission lose their completion. Agents without any stored
+/// submissions (e.g. completions from before submissions were recorded) are left untouched.
+pub async fn regrade_task(
+    Extension(pool): Extension<SqlitePool>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<RegradeReportDto>, AppError> {
+    let task = TaskInDb::read(&pool, task_id).await?;
+    let report = regrade(&pool, &task).await?;
+    Ok(Json(report))
+}
+
+/// Runs in one transaction so that a failure does not leave a half regraded leaderboard.
+async fn regrade(pool: &SqlitePool, task: &TaskInDb) -> anyhow::Result<RegradeReportDto> {
+    let mut tx = pool.begin().await?;
+    let completions = CompletionInDb::read_all_by_task(&mut *tx, task.id).await?;
+
+    let mut report = RegradeReportDto {
+        task_id: task.id,
+        changes: vec![],
+        unchanged: 0,
+    };
+
+    for mut completion in completions {
+        let mut submissions =
+            SubmissionInDb::read_all_by(&mut *tx, task.id, completion.agent_id).await?;
+        if submissions.is_empty() {
+            report.unchanged += 1;
+            continue;
+        }
+
+        for submission in &mut submissions {
+            let correct = is_correct(&task.solution, &submission.solution);
+            if submission.correct != correct {
+                submission.correct = correct;
+                submission.update_correct(&mut *tx).await?;
+            }
+        }
+
+        let first_correct = submissions
+            .iter()
+            .find(|submission| submission.correct)
+            .map(|submission| submission.submission_time);
+
+        match reclassify(&mut completion, first_correct) {
+            Some(change) => {
+                completion.update(&mut *tx).await?;
+                report.changes.push(RegradeAgentDto {
+                    agent_id: completion.agent_id,
+                    change,
+                    time_in_ms: completion.best_time_in_ms,
+                });
+            }
+            None => report.unchanged += 1,
+        }
+    }
+
+    tx.commit().await?;
+    Ok(report)
+}
+
+/// Completes the completion at the time of the first correct submission, or revokes it when
+/// there is none, and tells how that changed it.
+fn reclassify(
+    completion: &mut CompletionInDb,
+    first_correct: Option<DateTime<Utc>>,
+) -> Option<RegradeChangeDto> {
+    let previous_time_in_ms = completion.best_time_in_ms;
+    let was_completed = completion.completion_time.is_some();
+
+    match first_correct {
+        Some(first_correct) => {
+            completion.completion_time = None;
+            completion.best_time_in_ms = None;
+            completion.complete_at(first_correct);
+
+            if !was_completed {
+                Some(RegradeChangeDto::Completed)
+            } else if previous_time_in_ms != completion.best_time_in_ms {
+                Some(RegradeChangeDto::Retimed)
+            } else {
+                None
+            }
+        }
+        None if was_completed => {
+            completion.completion_time = None;
+            completion.best_time_in_ms = None;
+            completion.best_run_time_in_ms = None;
+            Some(RegradeChangeDto::Revoked)
+        }
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn completion(completed_after: Option<i64>) -> CompletionInDb {
+        let mut completion = CompletionInDb::new(Uuid::now_v7(), Uuid::now_v7());
+        if let Some(seconds) = completed_after {
+            completion.complete_at(completion.start_time + Duration::seconds(seconds));
+            completion.best_run_time_in_ms = Some(12);
+        }
+        completion
+    }
+
+    #[test]
+    fn completes_an_open_completion() {
+        let mut completion = completion(None);
+        let first_correct = completion.start_time + Duration::seconds(5);
+
+        let change = reclassify(&mut completion, Some(first_correct));
+
+        assert_eq!(change, Some(RegradeChangeDto::Completed));
+        assert_eq!(completion.completion_time, Some(first_correct));
+        assert_eq!(completion.best_time_in_ms, Some(5000));
+    }
+
+    #[test]
+    fn keeps_a_completion_with_the_same_time() {
+        let mut completion = completion(Some(5));
+        let first_correct = completion.start_time + Duration::seconds(5);
+
+        assert_eq!(reclassify(&mut completion, Some(first_correct)), None);
+        assert_eq!(completion.best_time_in_ms, Some(5000));
+        assert_eq!(completion.best_run_time_in_ms, Some(12));
+    }
+
+    #[test]
+    fn retimes_to_the_first_correct_submission() {
+        let mut earlier = completion(Some(5));
+        let first_correct = earlier.start_time + Duration::seconds(2);
+        assert_eq!(
+            reclassify(&mut earlier, Some(first_correct)),
+            Some(RegradeChangeDto::Retimed)
+        );
+        assert_eq!(earlier.best_time_in_ms, Some(2000));
+
+        // A later time replaces the best time too, it is not kept as the minimum.
+        let mut later = completion(Some(5));
+        let first_correct = later.start_time + Duration::seconds(9);
+        assert_eq!(
+            reclassify(&mut later, Some(first_correct)),
+            Some(RegradeChangeDto::Retimed)
+        );
+        assert_eq!(later.best_time_in_ms, Some(9000));
+    }
+
+    #[test]
+    fn revokes_a_completion_without_correct_submissions() {
+        let mut completion = completion(Some(5));
+
+        assert_eq!(
+            reclassify(&mut completion, None),
+            Some(RegradeChangeDto::Revoked)
+        );
+        assert_eq!(completion.completion_time, None);
+        assert_eq!(completion.best_time_in_ms, None);
+        assert_eq!(completion.best_run_time_in_ms, None);
+    }
+
+    #[test]
+    fn leaves_an_open_completion_without_correct_submissions() {
+        let mut completion = completion(None);
+
+        assert_eq!(reclassify(&mut completion, None), None);
+        assert_eq!(completion.completion_time, None);
+    }
+}
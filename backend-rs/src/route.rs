@@ -1,27 +1,40 @@
 use axum::{
-    routing::{delete, get, patch, post},
+    extract::DefaultBodyLimit,
+    routing::{any, delete, get, patch, post},
     Router,
 };
 
 use crate::{
     agent::{create_agent, read_agent, read_all_agents},
     auth::ValidateUserOrAdminTokenLayer,
+    bundle::{export_bundle, import_bundle, BUNDLE_BODY_LIMIT},
     check::check_task,
+    code_submission::{read_all_runtimes, submit_code},
     completion::delete_all_completions,
+    example_check::check_example,
+    examples::{add_task_examples, suggest_task_examples},
     health_check::health_checker_handler,
-    task::{create_task, delete_task, patch_task, read_all_tasks, read_all_tasks_admin, read_task},
+    import::import_tasks_from_dir,
+    leaderboard::read_leaderboard,
+    llm_proxy::{proxy_llm, LLM_BODY_LIMIT},
+    llm_usage::{read_all_llm_usage, read_llm_usage},
+    regrade::regrade_task,
+    task::{
+        create_task, delete_task, patch_task, purge_task, read_all_tasks, read_all_tasks_admin,
+        read_task, read_task_input, restore_task, TASK_BODY_LIMIT,
+    },
 };
 
 use tower_http::validate_request::ValidateRequestHeaderLayer;
 
 pub fn create_router(user_token: &str, admin_token: &str) -> Router {
-    let api_route = Router::new().nest(
+    Router::new().nest(
         "/api",
         create_public_router()
-            .merge(create_user_router(&user_token, &admin_token))
-            .nest("/admin", create_admin_router(&admin_token)),
-    );
-    api_route
+            .merge(create_user_router(user_token, admin_token))
+            .merge(create_llm_router())
+            .nest("/admin", create_admin_router(admin_token)),
+    )
 }
 
 fn create_public_router() -> Router {
@@ -30,21 +43,60 @@ fn create_public_router() -> Router {
 
 fn create_user_router(user_token: &str, admin_token: &str) -> Router {
     Router::new()
+        .route("/runtime", get(read_all_runtimes))
         .route("/agent", post(create_agent))
         .route("/agent", get(read_all_agents))
         .route("/agent/:id", get(read_agent))
+        .route("/task/:task_id/leaderboard", get(read_leaderboard))
         .route("/agent/:agentId/task", get(read_all_tasks))
         .route("/agent/:agent_id/task/:task_id", get(read_task))
+        .route("/agent/:agent_id/task/:task_id/input", get(read_task_input))
         .route("/agent/:agent_id/task/:task_id/check", post(check_task))
+        .route("/agent/:agent_id/task/:task_id/code", post(submit_code))
+        .route(
+            "/agent/:agent_id/task/:task_id/example-check",
+            post(check_example),
+        )
+        .route("/agent/:agent_id/llm-usage", get(read_llm_usage))
         .layer(ValidateUserOrAdminTokenLayer::new(user_token, admin_token))
 }
 
+/// Authenticated with the agent token instead of the user token, so LLM clients can send it as
+/// their API key.
+fn create_llm_router() -> Router {
+    Router::new().route(
+        "/agent/:agent_id/llm/:provider/*path",
+        any(proxy_llm).layer(DefaultBodyLimit::max(LLM_BODY_LIMIT)),
+    )
+}
+
 fn create_admin_router(user_token: &str) -> Router {
     Router::new()
         .route("/task", get(read_all_tasks_admin))
-        .route("/task", post(create_task))
+        .route(
+            "/task",
+            post(create_task).layer(DefaultBodyLimit::max(TASK_BODY_LIMIT)),
+        )
+        .route("/task/import", post(import_tasks_from_dir))
         .route("/task/:task_id", delete(delete_task))
-        .route("/task/:task_id", patch(patch_task))
+        .route(
+            "/task/:task_id",
+            patch(patch_task).layer(DefaultBodyLimit::max(TASK_BODY_LIMIT)),
+        )
+        .route("/task/:task_id/regrade", post(regrade_task))
+        .route("/task/:task_id/examples", post(add_task_examples))
+        .route(
+            "/task/:task_id/examples/suggest",
+            get(suggest_task_examples),
+        )
+        .route("/task/:task_id/restore", post(restore_task))
+        .route("/task/:task_id/purge", delete(purge_task))
         .route("/completion", delete(delete_all_completions))
+        .route("/llm/usage", get(read_all_llm_usage))
+        .route("/export", get(export_bundle))
+        .route(
+            "/import",
+            post(import_bundle).layer(DefaultBodyLimit::max(BUNDLE_BODY_LIMIT)),
+        )
         .route_layer(ValidateRequestHeaderLayer::bearer(user_token))
 }
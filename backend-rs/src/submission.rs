@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Executor, FromRow, Sqlite, SqlitePool};
+use uuid::Uuid;
+
+#[derive(FromRow, Debug)]
+pub struct SubmissionInDb {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub agent_id: Uuid,
+    pub solution: String,
+    pub correct: bool,
+    pub submission_time: DateTime<Utc>,
+    /// Source code of code submissions, `solution` then holds the output of the program.
+    pub code: Option<String>,
+    /// Runtime (language) of code submissions.
+    pub runtime: Option<String>,
+    /// Median run time of the code sent along with a correct solution, once it is benchmarked.
+    pub run_time_in_ms: Option<i64>,
+    /// Why that code could not be benchmarked.
+    pub benchmark_error: Option<String>,
+}
+
+impl SubmissionInDb {
+    pub fn new(task_id: Uuid, agent_id: Uuid, solution: String, correct: bool) -> Self {
+        Self {
+            id: Uuid::now_v7(),
+            task_id,
+            agent_id,
+            solution,
+            correct,
+            submission_time: Utc::now(),
+            code: None,
+            runtime: None,
+            run_time_in_ms: None,
+            benchmark_error: None,
+        }
+    }
+
+    pub async fn create(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(
+            r#"INSERT INTO submission (id, task_id, agent_id, solution, correct, submission_time, code, runtime, run_time_in_ms, benchmark_error) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"#,
+            self.id,
+            self.task_id,
+            self.agent_id,
+            self.solution,
+            self.correct,
+            self.submission_time,
+            self.code,
+            self.runtime,
+            self.run_time_in_ms,
+            self.benchmark_error,
+        )
+        .execute(conn.as_mut())
+        .await?;
+
+        Ok(())
+    }
+
+    /// All submissions of an agent for a task, oldest first.
+    pub async fn read_all_by(
+        executor: impl Executor<'_, Database = Sqlite>,
+        task_id: Uuid,
+        agent_id: Uuid,
+    ) -> anyhow::Result<Vec<Self>> {
+        let models = sqlx::query_as!(
+            Self,
+            r#"SELECT id as "id: uuid::Uuid", task_id as "task_id: uuid::Uuid", agent_id as "agent_id: uuid::Uuid", solution, correct, submission_time as "submission_time: chrono::DateTime<Utc>", code, runtime, run_time_in_ms, benchmark_error FROM submission WHERE task_id = ? AND agent_id = ? ORDER BY submission_time;"#,
+            task_id,
+            agent_id,
+        )
+        .fetch_all(executor)
+        .await?;
+        Ok(models)
+    }
+
+    pub async fn update_correct(
+        &self,
+        executor: impl Executor<'_, Database = Sqlite>,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"UPDATE submission SET correct = ? WHERE id = ?;"#,
+            self.correct,
+            self.id,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_benchmark(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut conn = pool.acquire().await?;
+        sqlx::query!(
+            r#"UPDATE submission SET run_time_in_ms = ?, benchmark_error = ? WHERE id = ?;"#,
+            self.run_time_in_ms,
+            self.benchmark_error,
+            self.id,
+        )
+        .execute(conn.as_mut())
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete_all(executor: impl Executor<'_, Database = Sqlite>) -> anyhow::Result<()> {
+        sqlx::query!(r#"DELETE FROM submission;"#)
+            .execute(executor)
+            .await?;
+
+        Ok(())
+    }
+}
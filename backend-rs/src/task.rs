@@ -1,24 +1,39 @@
+use anyhow::Context;
 use axum::{
     extract::{Path, Query},
+    http::header::{CONTENT_TYPE, ETAG},
+    response::IntoResponse,
     Extension, Json,
 };
-use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, SqlitePool};
+use chrono::{DateTime, Utc};
+use dto::{
+    TaskCreateDto, TaskDto, TaskExampleDto, TaskPatchDto, TaskTypeDto, TasksAdminDto, TasksDto,
+};
+use serde::Deserialize;
+use sqlx::{Executor, FromRow, Sqlite, SqlitePool};
 use uuid::Uuid;
 
 use crate::{
     agent::AgentInDb,
     completion::{self, CompletionInDb},
     error::{AppError, DtoValidationError},
+    task_input::{sha256_hex, TaskInputInDb},
     traits::DtoValidator,
 };
 
+pub const MAX_INPUT_LEN: usize = 4 * 1024 * 1024;
+pub const TASK_BODY_LIMIT: usize = 4 * MAX_INPUT_LEN;
+
 #[derive(FromRow, Debug)]
 pub struct TaskInDb {
     pub id: Uuid,
     pub name: String,
     pub task_json: String,
     pub solution: String,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub aoc_year: Option<i64>,
+    pub aoc_day: Option<i64>,
+    pub aoc_part: Option<i64>,
 }
 
 #[derive(FromRow, Debug)]
@@ -30,53 +45,6 @@ pub struct TaskWithCompletionInDb {
     pub completion_id: Option<Uuid>,
 }
 
-#[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TaskDto {
-    pub name: String,
-    pub taskType: TaskTypeDto,
-}
-
-#[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TasksDto {
-    pub id: Uuid,
-    pub name: String,
-    pub completed: bool,
-    pub time_in_ms: Option<i64>,
-}
-
-#[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TasksAdminDto {
-    pub id: Uuid,
-    pub name: String,
-    pub solution: String,
-}
-
-#[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TaskCreateDto {
-    pub name: String,
-    pub taskType: TaskTypeDto,
-    pub solution: String,
-}
-
-#[allow(non_snake_case)]
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TaskPatchDto {
-    pub name: Option<String>,
-    pub taskType: Option<TaskTypeDto>,
-    pub solution: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub enum TaskTypeDto {
-    SimpleTask { description: String },
-    AdventOfCodePartOne { description: String, input: String },
-    AdventOfCodePartTwo { description: String, input: String },
-}
-
 #[derive(Deserialize)]
 pub struct AgentToken {
     pub token: Uuid,
@@ -93,7 +61,7 @@ impl DtoValidator for TaskCreateDto {
             ));
         }
 
-        match &self.taskType {
+        match &self.task_type {
             TaskTypeDto::SimpleTask { description } => {
                 if description.len() > 32768 {
                     return Err(DtoValidationError(
@@ -101,29 +69,71 @@ impl DtoValidator for TaskCreateDto {
                     ));
                 }
             }
-            TaskTypeDto::AdventOfCodePartOne { description, input } => {
+            TaskTypeDto::AdventOfCodePartOne {
+                description,
+                input,
+                examples,
+            } => {
                 if description.len() > 32768 {
                     return Err(DtoValidationError(
                         "description too long (must be <=32768)".into(),
                     ));
                 }
-                if input.len() > 65536 {
-                    return Err(DtoValidationError(
-                        "input too long (must be <=65536)".into(),
-                    ));
+                if input.len() > MAX_INPUT_LEN {
+                    return Err(DtoValidationError(format!(
+                        "input too long (must be <={MAX_INPUT_LEN})"
+                    )));
                 }
+                examples.validate()?;
             }
-            TaskTypeDto::AdventOfCodePartTwo { description, input } => {
+            TaskTypeDto::AdventOfCodePartTwo {
+                description,
+                input,
+                examples,
+            }
+            | TaskTypeDto::CodeSubmission {
+                description,
+                input,
+                examples,
+            } => {
                 if description.len() > 32768 {
                     return Err(DtoValidationError(
                         "description too long (must be <=32768)".into(),
                     ));
                 }
-                if input.len() > 65536 {
-                    return Err(DtoValidationError(
-                        "input too long (must be <=65536)".into(),
-                    ));
+                if input.len() > MAX_INPUT_LEN {
+                    return Err(DtoValidationError(format!(
+                        "input too long (must be <={MAX_INPUT_LEN})"
+                    )));
                 }
+                examples.validate()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DtoValidator for Vec<TaskExampleDto> {
+    fn validate(&self) -> Result<(), DtoValidationError> {
+        if self.len() > 32 {
+            return Err(DtoValidationError(
+                "too many examples (must be <=32)".into(),
+            ));
+        }
+
+        for example in self {
+            if !(1..=2).contains(&example.part) {
+                return Err(DtoValidationError("example part must be 1 or 2".into()));
+            }
+            if example.input.len() > 32768 {
+                return Err(DtoValidationError(
+                    "example input too long (must be <=32768)".into(),
+                ));
+            }
+            if example.expected_output.len() > 32768 {
+                return Err(DtoValidationError(
+                    "example expected_output too long (must be <=32768)".into(),
+                ));
             }
         }
         Ok(())
@@ -136,9 +146,11 @@ pub async fn create_task(
 ) -> Result<Json<Uuid>, AppError> {
     dto.validate()?;
 
-    let task_json = serde_json::to_string(&dto.taskType).unwrap();
-    let model = TaskInDb::new(dto.name, task_json, dto.solution);
-    model.create(&pool).await?;
+    let mut model = TaskInDb::new(dto.name, String::new(), dto.solution);
+    let mut tx = pool.begin().await.context("cannot store task")?;
+    model.set_task_type(&mut *tx, dto.task_type).await?;
+    model.create(&mut *tx).await?;
+    tx.commit().await.context("cannot store task")?;
 
     Ok(Json(model.id))
 }
@@ -153,21 +165,69 @@ pub async fn read_task(
         return Err(AppError::Unauthorized);
     }
 
-    let model = TaskInDb::read(&pool, task_id).await?;
-    let task_type = serde_json::from_str(&model.task_json).unwrap();
+    let model = TaskInDb::read_active(&pool, task_id).await?;
+    let mut task_type = model.task_type(&pool).await?;
+    let input_sha256 = if task_type.is_input_hidden() {
+        task_type.take_input();
+        None
+    } else {
+        task_type.input().map(sha256_hex)
+    };
+
+    start_task(&pool, task_id, agent_id).await?;
+
+    Ok(Json(TaskDto {
+        name: model.name,
+        task_type,
+        input_sha256,
+    }))
+}
 
-    if CompletionInDb::read_by(&pool, task_id, agent_id)
+/// Returns the exact puzzle input as plain text. Like reading the task, this starts the timer.
+pub async fn read_task_input(
+    Extension(pool): Extension<SqlitePool>,
+    Path((agent_id, task_id)): Path<(Uuid, Uuid)>,
+    token: Query<AgentToken>,
+) -> Result<impl IntoResponse, AppError> {
+    let agent = AgentInDb::read(&pool, agent_id).await?;
+    if agent.token != token.token {
+        return Err(AppError::Unauthorized);
+    }
+
+    let model = TaskInDb::read_active(&pool, task_id).await?;
+    let mut task_type = model.task_type(&pool).await?;
+    if task_type.is_input_hidden() {
+        return Err(AppError::ValidationError(DtoValidationError(
+            "the input of this task is hidden".into(),
+        )));
+    }
+    let Some(input) = task_type.take_input() else {
+        return Err(AppError::ValidationError(DtoValidationError(
+            "task has no input".into(),
+        )));
+    };
+
+    start_task(&pool, task_id, agent_id).await?;
+
+    let etag = format!("\"{}\"", sha256_hex(&input));
+    Ok((
+        [
+            (CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+            (ETAG, etag),
+        ],
+        input,
+    ))
+}
+
+async fn start_task(pool: &SqlitePool, task_id: Uuid, agent_id: Uuid) -> anyhow::Result<()> {
+    if CompletionInDb::read_by(pool, task_id, agent_id)
         .await?
         .is_none()
     {
         let completion = CompletionInDb::new(task_id, agent_id);
-        completion.create(&pool).await?;
+        completion.create(pool).await?;
     }
-
-    Ok(Json(TaskDto {
-        name: model.name,
-        taskType: task_type,
-    }))
+    Ok(())
 }
 
 pub async fn read_all_tasks(
@@ -215,6 +275,7 @@ pub async fn read_all_tasks_admin(
             id: model.id,
             name: model.name,
             solution: model.solution,
+            archived_at: model.archived_at,
         };
 
         dtos.push(dto);
@@ -223,12 +284,36 @@ pub async fn read_all_tasks_admin(
     Ok(Json(dtos))
 }
 
+/// Archives the task: it is hidden from agents, but its completions and submissions are kept.
 pub async fn delete_task(
     Extension(pool): Extension<SqlitePool>,
     Path(task_id): Path<Uuid>,
+) -> Result<Json<Uuid>, AppError> {
+    let mut model = TaskInDb::read(&pool, task_id).await?;
+    model.archived_at = Some(Utc::now());
+    model.update(&pool).await?;
+
+    Ok(Json(model.id))
+}
+
+pub async fn restore_task(
+    Extension(pool): Extension<SqlitePool>,
+    Path(task_id): Path<Uuid>,
+) -> Result<Json<Uuid>, AppError> {
+    let mut model = TaskInDb::read(&pool, task_id).await?;
+    model.archived_at = None;
+    model.update(&pool).await?;
+
+    Ok(Json(model.id))
+}
+
+/// Removes the task together with all its completions and submissions.
+pub async fn purge_task(
+    Extension(pool): Extension<SqlitePool>,
+    Path(task_id): Path<Uuid>,
 ) -> Result<Json<Uuid>, AppError> {
     let model = TaskInDb::read(&pool, task_id).await?;
-    model.delete(&pool).await?;
+    model.purge(&pool).await?;
 
     Ok(Json(model.id))
 }
@@ -244,15 +329,26 @@ pub async fn patch_task(
         model.name = name;
     }
 
-    if let Some(task_type) = dto.taskType {
-        model.task_json = serde_json::to_string(&task_type).unwrap();
+    // The input is written by `set_task_type`, it must not stay behind if the update fails.
+    let mut tx = pool.begin().await.context("cannot store task")?;
+
+    if let Some(task_type) = dto.task_type {
+        model.set_task_type(&mut *tx, task_type).await?;
     }
 
     if let Some(solution) = dto.solution {
         model.solution = solution;
     }
 
-    model.update(&pool).await?;
+    if let Some(examples) = dto.examples {
+        examples.validate()?;
+        let mut task_type: TaskTypeDto = serde_json::from_str(&model.task_json).unwrap();
+        task_type.set_examples(examples)?;
+        model.task_json = serde_json::to_string(&task_type).unwrap();
+    }
+
+    model.update(&mut *tx).await?;
+    tx.commit().await.context("cannot store task")?;
 
     Ok(Json(model.id))
 }
@@ -264,34 +360,76 @@ impl TaskInDb {
             name,
             task_json,
             solution,
+            archived_at: None,
+            aoc_year: None,
+            aoc_day: None,
+            aoc_part: None,
         }
     }
 
-    pub async fn create(&self, pool: &SqlitePool) -> anyhow::Result<()> {
-        let mut conn = pool.acquire().await?;
+    /// Sets `task_json` from the task type. The input is stored in the `task_input` table.
+    pub async fn set_task_type(
+        &mut self,
+        executor: impl Executor<'_, Database = Sqlite>,
+        mut task_type: TaskTypeDto,
+    ) -> anyhow::Result<()> {
+        if let Some(input) = task_type.take_input() {
+            TaskInputInDb::new(self.id, &input)?.save(executor).await?;
+        }
+        self.task_json = serde_json::to_string(&task_type)?;
+        Ok(())
+    }
+
+    /// The task type including the input. Tasks created before inputs were stored separately
+    /// still have their input in `task_json`.
+    pub async fn task_type(
+        &self,
+        executor: impl Executor<'_, Database = Sqlite>,
+    ) -> anyhow::Result<TaskTypeDto> {
+        let mut task_type: TaskTypeDto = serde_json::from_str(&self.task_json)?;
+        if let Some(input) = TaskInputInDb::read(executor, self.id).await? {
+            task_type.set_input(input.input()?);
+        }
+        Ok(task_type)
+    }
+
+    pub async fn create(
+        &self,
+        executor: impl Executor<'_, Database = Sqlite>,
+    ) -> anyhow::Result<()> {
         sqlx::query!(
-            r#"INSERT INTO task (id, name, task_json, solution) VALUES (?, ?, ?, ?);"#,
+            r#"INSERT INTO task (id, name, task_json, solution, archived_at, aoc_year, aoc_day, aoc_part) VALUES (?, ?, ?, ?, ?, ?, ?, ?);"#,
             self.id,
             self.name,
             self.task_json,
             self.solution,
+            self.archived_at,
+            self.aoc_year,
+            self.aoc_day,
+            self.aoc_part,
         )
-        .execute(conn.as_mut())
+        .execute(executor)
         .await?;
 
         Ok(())
     }
 
-    pub async fn update(&self, pool: &SqlitePool) -> anyhow::Result<()> {
-        let mut conn = pool.acquire().await?;
+    pub async fn update(
+        &self,
+        executor: impl Executor<'_, Database = Sqlite>,
+    ) -> anyhow::Result<()> {
         sqlx::query!(
-            r#"UPDATE task SET name = ?, task_json = ?, solution = ? WHERE id = ?;"#,
+            r#"UPDATE task SET name = ?, task_json = ?, solution = ?, archived_at = ?, aoc_year = ?, aoc_day = ?, aoc_part = ? WHERE id = ?;"#,
             self.name,
             self.task_json,
             self.solution,
+            self.archived_at,
+            self.aoc_year,
+            self.aoc_day,
+            self.aoc_part,
             self.id,
         )
-        .execute(conn.as_mut())
+        .execute(executor)
         .await?;
 
         Ok(())
@@ -301,7 +439,7 @@ impl TaskInDb {
         let mut conn = pool.acquire().await?;
         let model = sqlx::query_as!(
             Self,
-            r#"SELECT id as "id: uuid::Uuid", name, task_json, solution FROM task WHERE id = ?;"#,
+            r#"SELECT id as "id: uuid::Uuid", name, task_json, solution, archived_at as "archived_at: chrono::DateTime<Utc>", aoc_year, aoc_day, aoc_part FROM task WHERE id = ?;"#,
             id,
         )
         .fetch_one(conn.as_mut())
@@ -309,11 +447,54 @@ impl TaskInDb {
         Ok(model)
     }
 
+    /// Like `read`, but archived tasks are treated as non-existent.
+    pub async fn read_active(pool: &SqlitePool, id: Uuid) -> anyhow::Result<Self> {
+        let mut conn = pool.acquire().await?;
+        let model = sqlx::query_as!(
+            Self,
+            r#"SELECT id as "id: uuid::Uuid", name, task_json, solution, archived_at as "archived_at: chrono::DateTime<Utc>", aoc_year, aoc_day, aoc_part FROM task WHERE id = ? AND archived_at IS NULL;"#,
+            id,
+        )
+        .fetch_one(conn.as_mut())
+        .await?;
+        Ok(model)
+    }
+
+    /// Whether there is a task with the id that is not archived.
+    pub async fn exists_active(pool: &SqlitePool, id: Uuid) -> anyhow::Result<bool> {
+        let mut conn = pool.acquire().await?;
+        let count = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!: i64" FROM task WHERE id = ? AND archived_at IS NULL;"#,
+            id,
+        )
+        .fetch_one(conn.as_mut())
+        .await?;
+        Ok(count > 0)
+    }
+
+    pub async fn read_by_puzzle(
+        executor: impl Executor<'_, Database = Sqlite>,
+        year: i64,
+        day: i64,
+        part: i64,
+    ) -> anyhow::Result<Option<Self>> {
+        let model = sqlx::query_as!(
+            Self,
+            r#"SELECT id as "id: uuid::Uuid", name, task_json, solution, archived_at as "archived_at: chrono::DateTime<Utc>", aoc_year, aoc_day, aoc_part FROM task WHERE aoc_year = ? AND aoc_day = ? AND aoc_part = ?;"#,
+            year,
+            day,
+            part,
+        )
+        .fetch_optional(executor)
+        .await?;
+        Ok(model)
+    }
+
     pub async fn read_all(pool: &SqlitePool) -> anyhow::Result<Vec<Self>> {
         let mut conn = pool.acquire().await?;
         let models = sqlx::query_as!(
             Self,
-            r#"SELECT id as "id: uuid::Uuid", name, task_json, solution FROM task;"#,
+            r#"SELECT id as "id: uuid::Uuid", name, task_json, solution, archived_at as "archived_at: chrono::DateTime<Utc>", aoc_year, aoc_day, aoc_part FROM task;"#,
         )
         .fetch_all(conn.as_mut())
         .await?;
@@ -327,7 +508,7 @@ impl TaskInDb {
         let mut conn = pool.acquire().await?;
         let models = sqlx::query_as!(
             TaskWithCompletionInDb,
-            r#"SELECT task.id as "id: uuid::Uuid", task.name, task.task_json, task.solution, completion.id as "completion_id: uuid::Uuid" FROM task LEFT JOIN completion ON task.id = completion.task_id AND completion.agent_id = ? AND completion.completion_time NOT NULL;"#,
+            r#"SELECT task.id as "id: uuid::Uuid", task.name, task.task_json, task.solution, completion.id as "completion_id: uuid::Uuid" FROM task LEFT JOIN completion ON task.id = completion.task_id AND completion.agent_id = ? AND completion.completion_time NOT NULL WHERE task.archived_at IS NULL;"#,
             agent_id,
         )
         .fetch_all(conn.as_mut())
@@ -335,11 +516,28 @@ impl TaskInDb {
         Ok(models)
     }
 
-    pub async fn delete(&self, pool: &SqlitePool) -> anyhow::Result<()> {
-        let mut conn = pool.acquire().await?;
+    pub async fn purge(&self, pool: &SqlitePool) -> anyhow::Result<()> {
+        let mut tx = pool.begin().await?;
+        sqlx::query!(r#"DELETE FROM submission WHERE task_id = ?;"#, self.id)
+            .execute(tx.as_mut())
+            .await?;
+        sqlx::query!(r#"DELETE FROM completion WHERE task_id = ?;"#, self.id)
+            .execute(tx.as_mut())
+            .await?;
+        sqlx::query!(r#"DELETE FROM task_input WHERE task_id = ?;"#, self.id)
+            .execute(tx.as_mut())
+            .await?;
+        // The tokens still count against the budget of the agents, for no task.
+        sqlx::query!(
+            r#"UPDATE llm_usage SET task_id = NULL WHERE task_id = ?;"#,
+            self.id
+        )
+        .execute(tx.as_mut())
+        .await?;
         sqlx::query!(r#"DELETE FROM task WHERE id = ?;"#, self.id)
-            .execute(conn.as_mut())
+            .execute(tx.as_mut())
             .await?;
+        tx.commit().await?;
 
         Ok(())
     }
@@ -0,0 +1,126 @@
+use std::io::{Read, Write};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use sha2::{Digest, Sha256};
+use sqlx::{Executor, FromRow, Sqlite};
+use uuid::Uuid;
+
+/// Inputs smaller than this are stored uncompressed.
+const COMPRESSION_THRESHOLD: usize = 4096;
+
+/// The puzzle input of a task, stored outside of `task.task_json` so it can be large.
+#[derive(FromRow, Debug)]
+pub struct TaskInputInDb {
+    pub task_id: Uuid,
+    pub compressed: bool,
+    pub data: Vec<u8>,
+}
+
+pub fn sha256_hex(input: &str) -> String {
+    format!("{:x}", Sha256::digest(input.as_bytes()))
+}
+
+impl TaskInputInDb {
+    pub fn new(task_id: Uuid, input: &str) -> anyhow::Result<Self> {
+        if input.len() >= COMPRESSION_THRESHOLD {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(input.as_bytes())?;
+            let data = encoder.finish()?;
+
+            if data.len() < input.len() {
+                return Ok(Self {
+                    task_id,
+                    compressed: true,
+                    data,
+                });
+            }
+        }
+
+        Ok(Self {
+            task_id,
+            compressed: false,
+            data: input.as_bytes().to_vec(),
+        })
+    }
+
+    pub fn input(&self) -> anyhow::Result<String> {
+        if !self.compressed {
+            return Ok(String::from_utf8(self.data.clone())?);
+        }
+
+        let mut input = String::new();
+        GzDecoder::new(self.data.as_slice()).read_to_string(&mut input)?;
+        Ok(input)
+    }
+
+    pub async fn save(&self, executor: impl Executor<'_, Database = Sqlite>) -> anyhow::Result<()> {
+        sqlx::query!(
+            r#"INSERT INTO task_input (task_id, compressed, data) VALUES (?, ?, ?) ON CONFLICT (task_id) DO UPDATE SET compressed = excluded.compressed, data = excluded.data;"#,
+            self.task_id,
+            self.compressed,
+            self.data,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn read(
+        executor: impl Executor<'_, Database = Sqlite>,
+        task_id: Uuid,
+    ) -> anyhow::Result<Option<Self>> {
+        let model = sqlx::query_as!(
+            Self,
+            r#"SELECT task_id as "task_id: uuid::Uuid", compressed, data FROM task_input WHERE task_id = ?;"#,
+            task_id,
+        )
+        .fetch_optional(executor)
+        .await?;
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_input_is_stored_as_is() {
+        let input = "1abc2\npqr3stu8vwx\n";
+
+        let model = TaskInputInDb::new(Uuid::now_v7(), input).unwrap();
+
+        assert!(!model.compressed);
+        assert_eq!(model.data, input.as_bytes());
+        assert_eq!(model.input().unwrap(), input);
+    }
+
+    #[test]
+    fn large_input_is_compressed_and_restored() {
+        let input = "#..#.##.äöü\n".repeat(COMPRESSION_THRESHOLD);
+
+        let model = TaskInputInDb::new(Uuid::now_v7(), &input).unwrap();
+
+        assert!(model.compressed);
+        assert!(model.data.len() < input.len());
+        assert_eq!(model.input().unwrap(), input);
+    }
+
+    #[test]
+    fn input_at_the_threshold_is_compressed() {
+        let input = "a".repeat(COMPRESSION_THRESHOLD);
+        let below = &input[1..];
+
+        assert!(
+            TaskInputInDb::new(Uuid::now_v7(), &input)
+                .unwrap()
+                .compressed
+        );
+        assert!(
+            !TaskInputInDb::new(Uuid::now_v7(), below)
+                .unwrap()
+                .compressed
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// A request and the response of the upstream API to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cassette {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+/// The request without its headers, so API keys are not stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub body: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// The whole body, for a streamed response all of its lines.
+    pub body: String,
+}
+
+impl RecordedRequest {
+    /// Hash of the method, the path and the body with sorted keys: the prompt and all parameters
+    /// like the model and the temperature.
+    pub fn key(&self) -> String {
+        let text = format!("{} {}\n{}", self.method, self.path, self.body);
+        format!("{:x}", Sha256::digest(text.as_bytes()))
+    }
+}
+
+/// The directory of the cassettes of a run. A request that is sent several times (e.g. the same
+/// prompt in another attempt) gets a cassette per time, `<key>-<n>.json`, so a replay serves
+/// the responses in the order they were recorded.
+pub struct Tape {
+    dir: PathBuf,
+    sent: Mutex<HashMap<String, usize>>,
+}
+
+impl Tape {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The file of the cassette for the next time the request is sent.
+    pub fn next_file(&self, request: &RecordedRequest) -> PathBuf {
+        let key = request.key();
+        let mut sent = self.sent.lock().unwrap();
+        let count = sent.entry(key.clone()).or_default();
+        let file = self.dir.join(format!("{key}-{count}.json"));
+        *count += 1;
+        file
+    }
+}
+
+impl Cassette {
+    pub fn load(file: &Path) -> anyhow::Result<Option<Self>> {
+        if !file.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&fs::read_to_string(file)?)?))
+    }
+
+    pub fn save(&self, file: &Path) -> anyhow::Result<()> {
+        fs::write(file, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, process};
+
+    use super::*;
+
+    fn request(body: &str) -> RecordedRequest {
+        RecordedRequest {
+            method: "POST".into(),
+            path: "/api/chat".into(),
+            body: serde_json::from_str(body).unwrap(),
+        }
+    }
+
+    fn cassette(request: &RecordedRequest, body: &str) -> Cassette {
+        Cassette {
+            request: request.clone(),
+            response: RecordedResponse {
+                status: 200,
+                content_type: Some("application/json".into()),
+                body: body.into(),
+            },
+        }
+    }
+
+    #[test]
+    fn key_ignores_the_order_of_json_keys() {
+        let a = request(r#"{"model": "m", "options": {"temperature": 0, "seed": 1}}"#);
+        let b = request(r#"{"options": {"seed": 1, "temperature": 0}, "model": "m"}"#);
+        assert_eq!(a.key(), b.key());
+
+        let other = request(r#"{"model": "m", "options": {"temperature": 1, "seed": 1}}"#);
+        assert_ne!(a.key(), other.key());
+    }
+
+    #[test]
+    fn same_request_is_recorded_and_replayed_in_order() {
+        let dir = env::temp_dir().join(format!("cassette-test-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let request = request(r#"{"model": "m", "messages": []}"#);
+        let key = request.key();
+
+        let recording = Tape::new(&dir);
+        let first = recording.next_file(&request);
+        let second = recording.next_file(&request);
+        assert_eq!(first, dir.join(format!("{key}-0.json")));
+        assert_eq!(second, dir.join(format!("{key}-1.json")));
+        cassette(&request, "first").save(&first).unwrap();
+        cassette(&request, "second").save(&second).unwrap();
+
+        let replay = Tape::new(&dir);
+        let replayed: Vec<_> = (0..3)
+            .map(|_| Cassette::load(&replay.next_file(&request)).unwrap())
+            .map(|cassette| cassette.map(|cassette| cassette.response.body))
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            replayed,
+            [Some("first".to_string()), Some("second".to_string()), None]
+        );
+    }
+}
@@ -0,0 +1,54 @@
+mod cassette;
+mod proxy;
+
+use std::{env, fs, net::SocketAddr, sync::Arc};
+
+use anyhow::bail;
+use axum::Router;
+use cassette::Tape;
+use proxy::{Mode, Proxy};
+use tokio::net::TcpListener;
+
+const DEFAULT_DIR: &str = "cassettes";
+const DEFAULT_ADDRESS: &str = "127.0.0.1:11435";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = env::args().collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let (mode, dir) = match args.as_slice() {
+        [_, "record", upstream, dir @ ..] if dir.len() <= 1 => {
+            let mode = Mode::Record {
+                client: reqwest::Client::new(),
+                upstream: upstream.trim_end_matches('/').to_string(),
+            };
+            (mode, dir.first().copied())
+        }
+        [_, "replay", dir @ ..] if dir.len() <= 1 => (Mode::Replay, dir.first().copied()),
+        _ => bail!("usage: cassette-rs [record <upstream url> | replay] [cassette dir]"),
+    };
+    let tape = Tape::new(dir.unwrap_or(DEFAULT_DIR));
+    match mode {
+        Mode::Record { .. } => fs::create_dir_all(tape.dir())?,
+        Mode::Replay if !tape.dir().is_dir() => bail!("no cassettes in {}", tape.dir().display()),
+        Mode::Replay => {}
+    }
+
+    let address: SocketAddr = env::var("ADDRESS")
+        .unwrap_or(DEFAULT_ADDRESS.into())
+        .parse()?;
+    match &mode {
+        Mode::Record { upstream, .. } => println!(
+            "Recording {upstream} into {} on {address}",
+            tape.dir().display()
+        ),
+        Mode::Replay => println!("Replaying {} on {address}", tape.dir().display()),
+    }
+
+    let router = Router::new()
+        .fallback(proxy::handle)
+        .with_state(Arc::new(Proxy { mode, tape }));
+    let listener = TcpListener::bind(address).await?;
+    axum::serve(listener, router).await?;
+    Ok(())
+}
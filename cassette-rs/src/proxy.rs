@@ -0,0 +1,141 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, Method, StatusCode, Uri,
+    },
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+
+use crate::cassette::{Cassette, RecordedRequest, RecordedResponse, Tape};
+
+pub enum Mode {
+    /// Forwards the requests to the upstream API, e.g. `http://192.168.3.2:1337`, and records
+    /// the responses.
+    Record {
+        client: reqwest::Client,
+        upstream: String,
+    },
+    /// Serves the recorded responses, without network.
+    Replay,
+}
+
+pub struct Proxy {
+    pub mode: Mode,
+    pub tape: Tape,
+}
+
+/// Handles every request, whatever the API: the Ollama and the OpenAI APIs are both JSON over
+/// HTTP.
+pub async fn handle(
+    State(proxy): State<Arc<Proxy>>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = uri
+        .path_and_query()
+        .map_or(uri.path(), |path| path.as_str())
+        .to_string();
+    let request_body = if body.is_empty() {
+        Value::Null
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(body) => body,
+            Err(error) => return error_response(StatusCode::BAD_REQUEST, error.to_string()),
+        }
+    };
+    let request = RecordedRequest {
+        method: method.to_string(),
+        path,
+        body: request_body,
+    };
+    let file = proxy.tape.next_file(&request);
+    let name = file.file_name().unwrap_or_default().to_string_lossy();
+
+    let result = match &proxy.mode {
+        Mode::Record { client, upstream } => {
+            // Errors are recorded too, so a replay fails at the same request.
+            let response = record(client, upstream, &request, &headers, body)
+                .await
+                .unwrap_or_else(|error| RecordedResponse {
+                    status: StatusCode::BAD_GATEWAY.as_u16(),
+                    content_type: Some("application/json".into()),
+                    body: json!({ "error": format!("{error:#}") }).to_string(),
+                });
+            let cassette = Cassette {
+                request: request.clone(),
+                response,
+            };
+            println!("{} {}: recorded {name}", request.method, request.path);
+            cassette.save(&file).map(|()| Some(cassette))
+        }
+        Mode::Replay => Cassette::load(&file).map(|cassette| {
+            match &cassette {
+                Some(_) => println!("{} {}: replayed {name}", request.method, request.path),
+                None => println!("{} {}: no {name}", request.method, request.path),
+            }
+            cassette
+        }),
+    };
+
+    match result {
+        Ok(Some(cassette)) => response(cassette.response),
+        Ok(None) => error_response(
+            StatusCode::NOT_FOUND,
+            format!("no cassette {name}, the request differs from the recorded run"),
+        ),
+        Err(error) => error_response(StatusCode::INTERNAL_SERVER_ERROR, format!("{error:#}")),
+    }
+}
+
+async fn record(
+    client: &reqwest::Client,
+    upstream: &str,
+    request: &RecordedRequest,
+    headers: &HeaderMap,
+    body: Bytes,
+) -> anyhow::Result<RecordedResponse> {
+    let method = reqwest::Method::from_bytes(request.method.as_bytes())?;
+    let mut upstream_request = client
+        .request(method, format!("{upstream}{}", request.path))
+        .body(body);
+    for header in [AUTHORIZATION, CONTENT_TYPE] {
+        if let Some(value) = headers.get(&header) {
+            upstream_request = upstream_request.header(header.as_str(), value.as_bytes());
+        }
+    }
+
+    let upstream_response = upstream_request.send().await?;
+    let status = upstream_response.status().as_u16();
+    let content_type = upstream_response
+        .headers()
+        .get(CONTENT_TYPE.as_str())
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = upstream_response.text().await?;
+    Ok(RecordedResponse {
+        status,
+        content_type,
+        body,
+    })
+}
+
+fn response(recorded: RecordedResponse) -> Response {
+    let status = StatusCode::from_u16(recorded.status).unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut response = (status, recorded.body).into_response();
+    if let Some(content_type) = recorded.content_type.and_then(|value| value.parse().ok()) {
+        response.headers_mut().insert(CONTENT_TYPE, content_type);
+    }
+    response
+}
+
+fn error_response(status: StatusCode, message: String) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
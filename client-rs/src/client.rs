@@ -0,0 +1,474 @@
+use std::time::Duration;
+
+use dto::*;
+use reqwest::{Method, RequestBuilder, Response};
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
+
+use crate::error::Error;
+
+/// How often and how long to wait before sending a request again after a transient error (the
+/// backend could not be reached, rate limit, bad gateway). The wait doubles with every retry.
+/// Requests other than `GET` are only sent again if they were rate limited or could not be sent
+/// at all, see `should_retry`.
+#[derive(Debug, Clone)]
+pub struct Retry {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+        }
+    }
+}
+
+/// Client of the backend API. Sends the user token (or the admin token, needed for the admin
+/// requests) as bearer token with every request.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+    retry: Retry,
+}
+
+/// The requests made on behalf of an agent, authenticated with its token.
+#[derive(Debug, Clone, Copy)]
+pub struct AgentClient<'a> {
+    client: &'a Client,
+    agent: AgentCreatedDto,
+}
+
+impl Client {
+    /// `base_url` includes the `/api` prefix, e.g. `http://32k.eu:8000/api`.
+    pub fn new(base_url: impl Into<String>, token: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: token.into(),
+            retry: Retry::default(),
+        }
+    }
+
+    pub fn with_retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn agent(&self, agent: AgentCreatedDto) -> AgentClient<'_> {
+        AgentClient {
+            client: self,
+            agent,
+        }
+    }
+
+    pub async fn health_check(&self) -> Result<HealthDto, Error> {
+        self.json(Method::GET, "/health", &[], None::<&()>).await
+    }
+
+    pub async fn read_all_runtimes(&self) -> Result<Vec<RuntimeDto>, Error> {
+        self.json(Method::GET, "/runtime", &[], None::<&()>).await
+    }
+
+    pub async fn create_agent(&self, dto: &AgentCreateDto) -> Result<AgentCreatedDto, Error> {
+        self.json(Method::POST, "/agent", &[], Some(dto)).await
+    }
+
+    pub async fn read_all_agents(&self) -> Result<Vec<AgentDto>, Error> {
+        self.json(Method::GET, "/agent", &[], None::<&()>).await
+    }
+
+    pub async fn read_agent(&self, agent_id: Uuid) -> Result<AgentDto, Error> {
+        self.json(Method::GET, &format!("/agent/{agent_id}"), &[], None::<&()>)
+            .await
+    }
+
+    pub async fn read_leaderboard(&self, task_id: Uuid) -> Result<Vec<LeaderboardEntryDto>, Error> {
+        let path = format!("/task/{task_id}/leaderboard");
+        self.json(Method::GET, &path, &[], None::<&()>).await
+    }
+
+    // Admin
+
+    pub async fn read_all_tasks_admin(&self) -> Result<Vec<TasksAdminDto>, Error> {
+        self.json(Method::GET, "/admin/task", &[], None::<&()>)
+            .await
+    }
+
+    /// Returns the id of the new task.
+    pub async fn create_task(&self, dto: &TaskCreateDto) -> Result<Uuid, Error> {
+        self.json(Method::POST, "/admin/task", &[], Some(dto)).await
+    }
+
+    pub async fn import_tasks_from_dir(
+        &self,
+        dto: &ImportRequestDto,
+    ) -> Result<ImportReportDto, Error> {
+        self.json(Method::POST, "/admin/task/import", &[], Some(dto))
+            .await
+    }
+
+    /// Archives the task, it can be restored.
+    pub async fn delete_task(&self, task_id: Uuid) -> Result<Uuid, Error> {
+        let path = format!("/admin/task/{task_id}");
+        self.json(Method::DELETE, &path, &[], None::<&()>).await
+    }
+
+    pub async fn patch_task(&self, task_id: Uuid, dto: &TaskPatchDto) -> Result<Uuid, Error> {
+        let path = format!("/admin/task/{task_id}");
+        self.json(Method::PATCH, &path, &[], Some(dto)).await
+    }
+
+    pub async fn regrade_task(&self, task_id: Uuid) -> Result<RegradeReportDto, Error> {
+        let path = format!("/admin/task/{task_id}/regrade");
+        self.json(Method::POST, &path, &[], None::<&()>).await
+    }
+
+    /// Returns all examples of the task.
+    pub async fn add_task_examples(
+        &self,
+        task_id: Uuid,
+        examples: &[TaskExampleDto],
+    ) -> Result<Vec<TaskExampleDto>, Error> {
+        let path = format!("/admin/task/{task_id}/examples");
+        self.json(Method::POST, &path, &[], Some(&examples)).await
+    }
+
+    pub async fn suggest_task_examples(&self, task_id: Uuid) -> Result<Vec<TaskExampleDto>, Error> {
+        let path = format!("/admin/task/{task_id}/examples/suggest");
+        self.json(Method::GET, &path, &[], None::<&()>).await
+    }
+
+    pub async fn restore_task(&self, task_id: Uuid) -> Result<Uuid, Error> {
+        let path = format!("/admin/task/{task_id}/restore");
+        self.json(Method::POST, &path, &[], None::<&()>).await
+    }
+
+    /// Deletes an archived task with its completions and submissions for good.
+    pub async fn purge_task(&self, task_id: Uuid) -> Result<Uuid, Error> {
+        let path = format!("/admin/task/{task_id}/purge");
+        self.json(Method::DELETE, &path, &[], None::<&()>).await
+    }
+
+    pub async fn delete_all_completions(&self) -> Result<String, Error> {
+        self.json(Method::DELETE, "/admin/completion", &[], None::<&()>)
+            .await
+    }
+
+    pub async fn read_all_llm_usage(&self) -> Result<Vec<LlmUsageDto>, Error> {
+        self.json(Method::GET, "/admin/llm/usage", &[], None::<&()>)
+            .await
+    }
+
+    pub async fn export_bundle(&self, agents: bool, completions: bool) -> Result<BundleDto, Error> {
+        let query = [
+            ("agents", agents.to_string()),
+            ("completions", completions.to_string()),
+        ];
+        self.json(Method::GET, "/admin/export", &query, None::<&()>)
+            .await
+    }
+
+    pub async fn import_bundle(
+        &self,
+        bundle: &BundleDto,
+        dry_run: bool,
+    ) -> Result<ImportBundleReportDto, Error> {
+        let query = [("dry_run", dry_run.to_string())];
+        self.json(Method::POST, "/admin/import", &query, Some(bundle))
+            .await
+    }
+
+    async fn json<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<&impl Serialize>,
+    ) -> Result<T, Error> {
+        let response = self.send(method, path, query, body).await?;
+        Ok(response.json().await?)
+    }
+
+    async fn text(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<String, Error> {
+        let response = self.send(method, path, query, None::<&()>).await?;
+        Ok(response.text().await?)
+    }
+
+    /// Sends the request, again after transient errors.
+    async fn send(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<&impl Serialize>,
+    ) -> Result<Response, Error> {
+        let mut backoff = self.retry.initial_backoff;
+        let mut retries = 0;
+        loop {
+            let request = self.request(method.clone(), path, query, body);
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status();
+                    Error::from_status(status, response.text().await.unwrap_or_default())
+                }
+                Err(e) => Error::Http(e),
+            };
+
+            if !should_retry(&method, &error) || retries >= self.retry.max_retries {
+                return Err(error);
+            }
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+            retries += 1;
+        }
+    }
+
+    fn request(
+        &self,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<&impl Serialize>,
+    ) -> RequestBuilder {
+        let mut request = self
+            .http
+            .request(method, format!("{}{path}", self.base_url))
+            .bearer_auth(&self.token);
+        if !query.is_empty() {
+            request = request.query(query);
+        }
+        if let Some(body) = body {
+            request = request.json(body);
+        }
+        request
+    }
+}
+
+impl AgentClient<'_> {
+    pub fn id(&self) -> Uuid {
+        self.agent.id
+    }
+
+    pub async fn read_all_tasks(&self) -> Result<Vec<TasksDto>, Error> {
+        let path = format!("/agent/{}/task", self.agent.id);
+        self.client
+            .json(Method::GET, &path, &self.token(), None::<&()>)
+            .await
+    }
+
+    /// Starts the timer of the task.
+    pub async fn read_task(&self, task_id: Uuid) -> Result<TaskDto, Error> {
+        let path = self.task_path(task_id, "");
+        self.client
+            .json(Method::GET, &path, &self.token(), None::<&()>)
+            .await
+    }
+
+    /// The exact puzzle input. Starts the timer of the task.
+    pub async fn read_task_input(&self, task_id: Uuid) -> Result<String, Error> {
+        let path = self.task_path(task_id, "/input");
+        self.client.text(Method::GET, &path, &self.token()).await
+    }
+
+    pub async fn check_task(
+        &self,
+        task_id: Uuid,
+        dto: &CheckTaskRequestDto,
+    ) -> Result<CheckTaskResponseDto, Error> {
+        let path = self.task_path(task_id, "/check");
+        self.client
+            .json(Method::POST, &path, &self.token(), Some(dto))
+            .await
+    }
+
+    pub async fn submit_code(
+        &self,
+        task_id: Uuid,
+        dto: &CodeSubmissionRequestDto,
+    ) -> Result<CodeSubmissionResponseDto, Error> {
+        let path = self.task_path(task_id, "/code");
+        self.client
+            .json(Method::POST, &path, &self.token(), Some(dto))
+            .await
+    }
+
+    /// Checks a solution against an example of the task, without starting or completing it.
+    pub async fn check_example(
+        &self,
+        task_id: Uuid,
+        dto: &ExampleCheckRequestDto,
+    ) -> Result<CheckTaskResponseDto, Error> {
+        let path = self.task_path(task_id, "/example-check");
+        self.client
+            .json(Method::POST, &path, &self.token(), Some(dto))
+            .await
+    }
+
+    /// The tokens and latency of the requests through the LLM proxy of the backend.
+    pub async fn read_llm_usage(&self) -> Result<LlmUsageDto, Error> {
+        let path = format!("/agent/{}/llm-usage", self.agent.id);
+        self.client
+            .json(Method::GET, &path, &self.token(), None::<&()>)
+            .await
+    }
+
+    fn task_path(&self, task_id: Uuid, suffix: &str) -> String {
+        format!("/agent/{}/task/{task_id}{suffix}", self.agent.id)
+    }
+
+    fn token(&self) -> [(&'static str, String); 1] {
+        [("token", self.agent.token.to_string())]
+    }
+}
+
+/// `GET` requests are sent again after any transient error. Anything else may have been processed
+/// already when a timeout or a gateway error comes back, so it is only sent again if the backend
+/// rejected it with a rate limit or the connection could not be established.
+fn should_retry(method: &Method, error: &Error) -> bool {
+    if method == Method::GET {
+        return error.is_transient();
+    }
+    match error {
+        Error::TooManyRequests => true,
+        Error::Http(e) => e.is_connect(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use reqwest::StatusCode;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    fn server_error(status: StatusCode) -> Error {
+        Error::from_status(status, String::new())
+    }
+
+    /// Answers every request with `status` and counts the requests.
+    async fn serve(status: StatusCode) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/api", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buffer = [0; 4096];
+                let _ = stream.read(&mut buffer).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response =
+                    format!("HTTP/1.1 {status}\r\ncontent-length: 0\r\nconnection: close\r\n\r\n");
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (base_url, requests)
+    }
+
+    fn client(base_url: &str) -> Client {
+        Client::new(base_url, "token").with_retry(Retry {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+        })
+    }
+
+    #[test]
+    fn retries_gateway_errors_only_for_get() {
+        for status in [
+            StatusCode::BAD_GATEWAY,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::GATEWAY_TIMEOUT,
+        ] {
+            assert!(should_retry(&Method::GET, &server_error(status)));
+            assert!(!should_retry(&Method::POST, &server_error(status)));
+            assert!(!should_retry(&Method::DELETE, &server_error(status)));
+        }
+    }
+
+    #[test]
+    fn retries_rate_limits_for_every_method() {
+        let error = server_error(StatusCode::TOO_MANY_REQUESTS);
+        assert!(should_retry(&Method::GET, &error));
+        assert!(should_retry(&Method::POST, &error));
+    }
+
+    #[test]
+    fn does_not_retry_other_errors() {
+        for status in [
+            StatusCode::BAD_REQUEST,
+            StatusCode::UNAUTHORIZED,
+            StatusCode::NOT_FOUND,
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ] {
+            assert!(!should_retry(&Method::GET, &server_error(status)));
+            assert!(!should_retry(&Method::POST, &server_error(status)));
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_connection_errors_for_every_method() {
+        // Nothing listens on the port once the listener is dropped.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/api", listener.local_addr().unwrap());
+        drop(listener);
+
+        let error = client(&base_url)
+            .send(Method::POST, "/agent", &[], None::<&()>)
+            .await
+            .unwrap_err();
+        assert!(matches!(&error, Error::Http(e) if e.is_connect()));
+        assert!(should_retry(&Method::POST, &error));
+    }
+
+    #[tokio::test]
+    async fn sends_get_again_after_gateway_error() {
+        let (base_url, requests) = serve(StatusCode::SERVICE_UNAVAILABLE).await;
+        let error = client(&base_url).health_check().await.unwrap_err();
+        assert!(
+            matches!(error, Error::Server { status, .. } if status == StatusCode::SERVICE_UNAVAILABLE)
+        );
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn sends_post_once_after_gateway_error() {
+        let (base_url, requests) = serve(StatusCode::SERVICE_UNAVAILABLE).await;
+        let dto = AgentCreateDto {
+            name: "agent".into(),
+        };
+        let error = client(&base_url).create_agent(&dto).await.unwrap_err();
+        assert!(matches!(error, Error::Server { .. }));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn sends_post_again_after_rate_limit() {
+        let (base_url, requests) = serve(StatusCode::TOO_MANY_REQUESTS).await;
+        let dto = AgentCreateDto {
+            name: "agent".into(),
+        };
+        let error = client(&base_url).create_agent(&dto).await.unwrap_err();
+        assert!(matches!(error, Error::TooManyRequests));
+        assert_eq!(requests.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,108 @@
+use reqwest::StatusCode;
+use thiserror::Error;
+
+/// The error statuses of the backend, plus failures to reach it.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The request was rejected, e.g. a solution that is too long or a task that was not started.
+    #[error("invalid request: {0}")]
+    Validation(String),
+    /// Wrong bearer token, or wrong agent token.
+    #[error("unauthorized")]
+    Unauthorized,
+    #[error("not found")]
+    NotFound,
+    #[error("request body too large")]
+    PayloadTooLarge,
+    /// Rate limited, e.g. the example checks.
+    #[error("too many requests")]
+    TooManyRequests,
+    #[error("server error ({status}): {message}")]
+    Server { status: StatusCode, message: String },
+    #[error("unexpected status ({status}): {message}")]
+    Status { status: StatusCode, message: String },
+    /// The backend could not be reached, or the response could not be read.
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+}
+
+impl Error {
+    pub fn from_status(status: StatusCode, message: String) -> Self {
+        match status {
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                Error::Validation(message)
+            }
+            StatusCode::UNAUTHORIZED => Error::Unauthorized,
+            StatusCode::NOT_FOUND => Error::NotFound,
+            StatusCode::PAYLOAD_TOO_LARGE => Error::PayloadTooLarge,
+            StatusCode::TOO_MANY_REQUESTS => Error::TooManyRequests,
+            _ if status.is_server_error() => Error::Server { status, message },
+            _ => Error::Status { status, message },
+        }
+    }
+
+    /// Whether the same request may succeed when sent again later.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::TooManyRequests => true,
+            Error::Server { status, .. } => matches!(
+                *status,
+                StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            ),
+            Error::Http(e) => e.is_connect() || e.is_timeout(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_statuses_to_errors() {
+        let error = |status| Error::from_status(status, "message".into());
+        assert!(matches!(error(StatusCode::BAD_REQUEST), Error::Validation(m) if m == "message"));
+        assert!(matches!(
+            error(StatusCode::UNPROCESSABLE_ENTITY),
+            Error::Validation(_)
+        ));
+        assert!(matches!(
+            error(StatusCode::UNAUTHORIZED),
+            Error::Unauthorized
+        ));
+        assert!(matches!(error(StatusCode::NOT_FOUND), Error::NotFound));
+        assert!(matches!(
+            error(StatusCode::PAYLOAD_TOO_LARGE),
+            Error::PayloadTooLarge
+        ));
+        assert!(matches!(
+            error(StatusCode::TOO_MANY_REQUESTS),
+            Error::TooManyRequests
+        ));
+        assert!(matches!(
+            error(StatusCode::BAD_GATEWAY),
+            Error::Server { status: StatusCode::BAD_GATEWAY, message } if message == "message"
+        ));
+        assert!(matches!(
+            error(StatusCode::CONFLICT),
+            Error::Status {
+                status: StatusCode::CONFLICT,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn only_some_server_errors_are_transient() {
+        let error = |status| Error::from_status(status, String::new());
+        assert!(error(StatusCode::TOO_MANY_REQUESTS).is_transient());
+        assert!(error(StatusCode::BAD_GATEWAY).is_transient());
+        assert!(error(StatusCode::SERVICE_UNAVAILABLE).is_transient());
+        assert!(error(StatusCode::GATEWAY_TIMEOUT).is_transient());
+        assert!(!error(StatusCode::INTERNAL_SERVER_ERROR).is_transient());
+        assert!(!error(StatusCode::BAD_REQUEST).is_transient());
+    }
+}
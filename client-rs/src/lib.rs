@@ -0,0 +1,39 @@
+//! Typed client of the backend API: one async method per route, with the bearer token and the
+//! agent token added to the requests, retries after transient errors and the error statuses
+//! mapped to `Error`.
+//!
+//! ```no_run
+//! # async fn solve() -> Result<(), client::Error> {
+//! use client::{AgentCreateDto, CheckTaskRequestDto, Client};
+//!
+//! let client = Client::new("http://32k.eu:8000/api", "<USER_TOKEN>");
+//! let agent = client
+//!     .create_agent(&AgentCreateDto {
+//!         name: "my-agent".into(),
+//!     })
+//!     .await?;
+//!
+//! let agent = client.agent(agent);
+//! for task in agent.read_all_tasks().await? {
+//!     if task.completed {
+//!         continue;
+//!     }
+//!     let input = agent.read_task_input(task.id).await?;
+//!     let check = CheckTaskRequestDto {
+//!         solution: input.lines().count().to_string(),
+//!         code: None,
+//!     };
+//!     println!("{}: {}", task.name, agent.check_task(task.id, &check).await?.correct);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The DTOs are re-exported from `dto-rs`, shared with the backend.
+
+mod client;
+mod error;
+
+pub use client::{AgentClient, Client, Retry};
+pub use dto::*;
+pub use error::Error;
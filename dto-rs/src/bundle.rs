@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::TaskCreateDto;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleDto {
+    pub version: u32,
+    pub tasks: Vec<BundleTaskDto>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agents: Option<Vec<BundleAgentDto>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub completions: Option<Vec<BundleCompletionDto>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleTaskDto {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub task: TaskCreateDto,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub aoc_year: Option<i64>,
+    pub aoc_day: Option<i64>,
+    pub aoc_part: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleAgentDto {
+    pub id: Uuid,
+    pub token: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleCompletionDto {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub agent_id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub completion_time: Option<DateTime<Utc>>,
+    pub best_time_in_ms: Option<i64>,
+    pub best_run_time_in_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportCountsDto {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportBundleReportDto {
+    pub dry_run: bool,
+    pub applied: bool,
+    pub tasks: ImportCountsDto,
+    pub agents: ImportCountsDto,
+    pub completions: ImportCountsDto,
+    pub conflicts: Vec<String>,
+}
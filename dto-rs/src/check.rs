@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckTaskRequestDto {
+    pub solution: String,
+    /// Rust source that prints the solution, benchmarked if the solution is correct.
+    pub code: Option<String>,
+}
+
+/// The response of both the check and the example check.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckTaskResponseDto {
+    pub correct: bool,
+}
+
+impl CheckTaskResponseDto {
+    pub fn new(correct: bool) -> Self {
+        Self { correct }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExampleCheckRequestDto {
+    pub example: usize,
+    pub solution: String,
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeSubmissionRequestDto {
+    pub code: String,
+    /// Name of the runtime (language), `rust` if not given.
+    pub runtime: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuntimeDto {
+    pub name: String,
+    pub source_file: String,
+    pub template: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CodeSubmissionResponseDto {
+    pub correct: bool,
+    pub compile_error: Option<String>,
+    /// The compiler errors and warnings of a failed build.
+    pub diagnostics: Option<Vec<DiagnosticDto>>,
+    pub timed_out: bool,
+    pub exit_code: Option<i32>,
+    pub output: Option<String>,
+    pub stderr: Option<String>,
+    pub wall_time_ms: Option<u64>,
+    pub cpu_time_ms: Option<u64>,
+}
+
+/// A compiler error or warning, as reported by `sandbox-rs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticDto {
+    /// `error` or `warning`.
+    pub level: String,
+    /// Error code like `E0308`, if any.
+    pub code: Option<String>,
+    pub message: String,
+    /// Location of the primary span.
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Label of the primary span, e.g. "expected `i32`, found `&str`".
+    pub label: Option<String>,
+    /// Notes and helps attached to the diagnostic.
+    pub notes: Vec<String>,
+    pub suggestions: Vec<SuggestionDto>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SuggestionDto {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub replacement: String,
+}
@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A directory on the machine of the backend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportRequestDto {
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportReportDto {
+    pub created: Vec<Uuid>,
+    pub updated: Vec<Uuid>,
+    pub unchanged: Vec<Uuid>,
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntryDto {
+    pub rank: usize,
+    pub agent_id: Uuid,
+    pub agent_name: String,
+    pub run_time_in_ms: i64,
+    pub time_in_ms: Option<i64>,
+    /// Tokens the agent used for the task through the LLM proxy of the backend, if any.
+    pub llm_tokens: Option<i64>,
+}
@@ -0,0 +1,38 @@
+//! The request and response bodies of the backend API, shared by `backend-rs` and its clients.
+//!
+//! The JSON representation is stable and pinned by the golden JSON of the round-trip tests:
+//! changing it is a breaking change that bumps `VERSION`.
+//! `TaskTypeDto` is not `#[non_exhaustive]` on purpose: a new variant fails every `match` on it.
+
+mod agent;
+mod bundle;
+mod check;
+mod code_submission;
+mod health;
+mod import;
+mod leaderboard;
+mod llm_usage;
+mod regrade;
+mod task;
+
+pub use agent::{AgentCreateDto, AgentCreatedDto, AgentDto};
+pub use bundle::{
+    BundleAgentDto, BundleCompletionDto, BundleDto, BundleTaskDto, ImportBundleReportDto,
+    ImportCountsDto,
+};
+pub use check::{CheckTaskRequestDto, CheckTaskResponseDto, ExampleCheckRequestDto};
+pub use code_submission::{
+    CodeSubmissionRequestDto, CodeSubmissionResponseDto, DiagnosticDto, RuntimeDto, SuggestionDto,
+};
+pub use health::HealthDto;
+pub use import::{ImportReportDto, ImportRequestDto};
+pub use leaderboard::LeaderboardEntryDto;
+pub use llm_usage::{LlmTaskUsageDto, LlmUsageCountsDto, LlmUsageDto};
+pub use regrade::{RegradeAgentDto, RegradeChangeDto, RegradeReportDto};
+pub use task::{
+    ExamplesNotSupported, TaskCreateDto, TaskDto, TaskExampleDto, TaskPatchDto, TaskTypeDto,
+    TasksAdminDto, TasksDto,
+};
+
+/// Version of the JSON representation of the DTOs.
+pub const VERSION: u32 = 1;
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The LLM usage of an agent through the proxy of the backend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmUsageDto {
+    pub agent_id: Uuid,
+    /// Tokens the agent may use in total, `None` if unlimited.
+    pub token_budget: Option<i64>,
+    pub total: LlmUsageCountsDto,
+    /// The usage per task, `task_id` is `None` for requests without a task.
+    pub tasks: Vec<LlmTaskUsageDto>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LlmUsageCountsDto {
+    pub requests: i64,
+    pub prompt_tokens: i64,
+    pub completion_tokens: i64,
+    /// The time spent waiting for the upstream providers.
+    pub latency_ms: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmTaskUsageDto {
+    pub task_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub usage: LlmUsageCountsDto,
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RegradeChangeDto {
+    Completed,
+    Revoked,
+    Retimed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegradeAgentDto {
+    pub agent_id: Uuid,
+    pub change: RegradeChangeDto,
+    pub time_in_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegradeReportDto {
+    pub task_id: Uuid,
+    pub changes: Vec<RegradeAgentDto>,
+    pub unchanged: usize,
+}
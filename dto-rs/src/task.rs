@@ -0,0 +1,160 @@
+use std::{error::Error, fmt};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskDto {
+    pub name: String,
+    #[serde(rename = "taskType")]
+    pub task_type: TaskTypeDto,
+    pub input_sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TasksDto {
+    pub id: Uuid,
+    pub name: String,
+    pub completed: bool,
+    pub time_in_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TasksAdminDto {
+    pub id: Uuid,
+    pub name: String,
+    pub solution: String,
+    pub archived_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskCreateDto {
+    pub name: String,
+    #[serde(rename = "taskType")]
+    pub task_type: TaskTypeDto,
+    pub solution: String,
+}
+
+/// Fields that are `None` are left unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskPatchDto {
+    pub name: Option<String>,
+    #[serde(rename = "taskType")]
+    pub task_type: Option<TaskTypeDto>,
+    pub solution: Option<String>,
+    pub examples: Option<Vec<TaskExampleDto>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TaskTypeDto {
+    SimpleTask {
+        description: String,
+    },
+    AdventOfCodePartOne {
+        description: String,
+        input: String,
+        #[serde(default)]
+        examples: Vec<TaskExampleDto>,
+    },
+    AdventOfCodePartTwo {
+        description: String,
+        input: String,
+        #[serde(default)]
+        examples: Vec<TaskExampleDto>,
+    },
+    /// Solved by submitting Rust code instead of an answer. The backend runs the code against the
+    /// input, which is never shown to agents.
+    CodeSubmission {
+        description: String,
+        input: String,
+        #[serde(default)]
+        examples: Vec<TaskExampleDto>,
+    },
+}
+
+/// A worked example from the puzzle description, so agents can test their code before submitting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskExampleDto {
+    pub part: u8,
+    pub input: String,
+    pub expected_output: String,
+}
+
+/// Returned when setting the examples of a task type without examples.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExamplesNotSupported;
+
+impl fmt::Display for ExamplesNotSupported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "task type does not support examples")
+    }
+}
+
+impl Error for ExamplesNotSupported {}
+
+impl TaskTypeDto {
+    pub fn description(&self) -> &str {
+        match self {
+            TaskTypeDto::SimpleTask { description } => description,
+            TaskTypeDto::AdventOfCodePartOne { description, .. } => description,
+            TaskTypeDto::AdventOfCodePartTwo { description, .. } => description,
+            TaskTypeDto::CodeSubmission { description, .. } => description,
+        }
+    }
+
+    pub fn input(&self) -> Option<&str> {
+        match self {
+            TaskTypeDto::SimpleTask { .. } => None,
+            TaskTypeDto::AdventOfCodePartOne { input, .. } => Some(input),
+            TaskTypeDto::AdventOfCodePartTwo { input, .. } => Some(input),
+            TaskTypeDto::CodeSubmission { input, .. } => Some(input),
+        }
+    }
+
+    /// Moves the input out of the task type, leaving an empty input behind.
+    pub fn take_input(&mut self) -> Option<String> {
+        match self {
+            TaskTypeDto::SimpleTask { .. } => None,
+            TaskTypeDto::AdventOfCodePartOne { input, .. } => Some(std::mem::take(input)),
+            TaskTypeDto::AdventOfCodePartTwo { input, .. } => Some(std::mem::take(input)),
+            TaskTypeDto::CodeSubmission { input, .. } => Some(std::mem::take(input)),
+        }
+    }
+
+    pub fn set_input(&mut self, new_input: String) {
+        match self {
+            TaskTypeDto::SimpleTask { .. } => {}
+            TaskTypeDto::AdventOfCodePartOne { input, .. } => *input = new_input,
+            TaskTypeDto::AdventOfCodePartTwo { input, .. } => *input = new_input,
+            TaskTypeDto::CodeSubmission { input, .. } => *input = new_input,
+        }
+    }
+
+    /// Whether agents may see the input. Code submissions are run by the backend instead.
+    pub fn is_input_hidden(&self) -> bool {
+        matches!(self, TaskTypeDto::CodeSubmission { .. })
+    }
+
+    pub fn examples(&self) -> &[TaskExampleDto] {
+        match self {
+            TaskTypeDto::SimpleTask { .. } => &[],
+            TaskTypeDto::AdventOfCodePartOne { examples, .. } => examples,
+            TaskTypeDto::AdventOfCodePartTwo { examples, .. } => examples,
+            TaskTypeDto::CodeSubmission { examples, .. } => examples,
+        }
+    }
+
+    pub fn set_examples(
+        &mut self,
+        new_examples: Vec<TaskExampleDto>,
+    ) -> Result<(), ExamplesNotSupported> {
+        match self {
+            TaskTypeDto::SimpleTask { .. } => return Err(ExamplesNotSupported),
+            TaskTypeDto::AdventOfCodePartOne { examples, .. } => *examples = new_examples,
+            TaskTypeDto::AdventOfCodePartTwo { examples, .. } => *examples = new_examples,
+            TaskTypeDto::CodeSubmission { examples, .. } => *examples = new_examples,
+        }
+        Ok(())
+    }
+}
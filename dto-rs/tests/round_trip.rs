@@ -0,0 +1,346 @@
+use std::fmt::Debug;
+
+use chrono::{TimeZone, Utc};
+use dto::*;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+const _: () = assert!(VERSION == 1, "update the golden JSON below");
+
+/// Checks that `dto` serializes to exactly `golden` and deserializes back to `dto`.
+fn assert_round_trip<T>(dto: T, golden: Value)
+where
+    T: Serialize + DeserializeOwned + PartialEq + Debug,
+{
+    let json = serde_json::to_value(&dto).unwrap();
+    assert_eq!(json, golden);
+    let back: T = serde_json::from_value(json).unwrap();
+    assert_eq!(back, dto);
+}
+
+fn example() -> TaskExampleDto {
+    TaskExampleDto {
+        part: 1,
+        input: "1\n2\n".into(),
+        expected_output: "3".into(),
+    }
+}
+
+fn example_json() -> Value {
+    json!({ "part": 1, "input": "1\n2\n", "expected_output": "3" })
+}
+
+/// One instance of every variant with its golden JSON. The match fails to compile when a variant
+/// is added, so every variant gets a fixture.
+fn task_types() -> Vec<(TaskTypeDto, Value)> {
+    let all = vec![
+        TaskTypeDto::SimpleTask {
+            description: "Say hello".into(),
+        },
+        TaskTypeDto::AdventOfCodePartOne {
+            description: "Sum".into(),
+            input: "1\n2\n".into(),
+            examples: vec![example()],
+        },
+        TaskTypeDto::AdventOfCodePartTwo {
+            description: "Product".into(),
+            input: "1\n2\n".into(),
+            examples: vec![],
+        },
+        TaskTypeDto::CodeSubmission {
+            description: "Max".into(),
+            input: "1\n2\n".into(),
+            examples: vec![example()],
+        },
+    ];
+    all.into_iter()
+        .map(|task_type| {
+            let golden = match &task_type {
+                TaskTypeDto::SimpleTask { .. } => {
+                    json!({ "SimpleTask": { "description": "Say hello" } })
+                }
+                TaskTypeDto::AdventOfCodePartOne { .. } => json!({ "AdventOfCodePartOne": {
+                    "description": "Sum",
+                    "input": "1\n2\n",
+                    "examples": [example_json()],
+                } }),
+                TaskTypeDto::AdventOfCodePartTwo { .. } => json!({ "AdventOfCodePartTwo": {
+                    "description": "Product",
+                    "input": "1\n2\n",
+                    "examples": [],
+                } }),
+                TaskTypeDto::CodeSubmission { .. } => json!({ "CodeSubmission": {
+                    "description": "Max",
+                    "input": "1\n2\n",
+                    "examples": [example_json()],
+                } }),
+            };
+            (task_type, golden)
+        })
+        .collect()
+}
+
+#[test]
+fn task_type_variants() {
+    for (task_type, golden) in task_types() {
+        assert_round_trip(task_type, golden);
+    }
+}
+
+#[test]
+fn task_type_examples_default_to_empty() {
+    let task_type: TaskTypeDto = serde_json::from_value(json!({ "AdventOfCodePartOne": {
+        "description": "Sum",
+        "input": "1\n2\n",
+    } }))
+    .unwrap();
+    assert_eq!(task_type.examples(), &[]);
+}
+
+#[test]
+fn task_type_set_examples() {
+    for (mut task_type, _) in task_types() {
+        let supported = !matches!(task_type, TaskTypeDto::SimpleTask { .. });
+        assert_eq!(
+            task_type.set_examples(vec![example()]).is_ok(),
+            supported,
+            "{task_type:?}"
+        );
+    }
+}
+
+#[test]
+fn task_dtos() {
+    let (task_type, task_type_json) = task_types().remove(1);
+    assert_round_trip(
+        TaskDto {
+            name: "Day 1".into(),
+            task_type: task_type.clone(),
+            input_sha256: Some("ab12".into()),
+        },
+        json!({ "name": "Day 1", "taskType": task_type_json, "input_sha256": "ab12" }),
+    );
+    assert_round_trip(
+        TaskCreateDto {
+            name: "Day 1".into(),
+            task_type,
+            solution: "3".into(),
+        },
+        json!({ "name": "Day 1", "taskType": task_type_json, "solution": "3" }),
+    );
+    assert_round_trip(
+        TaskPatchDto {
+            name: Some("Day 1".into()),
+            ..Default::default()
+        },
+        json!({ "name": "Day 1", "taskType": null, "solution": null, "examples": null }),
+    );
+    assert_round_trip(
+        TasksDto {
+            id: Uuid::nil(),
+            name: "Day 1".into(),
+            completed: true,
+            time_in_ms: Some(42),
+        },
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": "Day 1",
+            "completed": true,
+            "time_in_ms": 42,
+        }),
+    );
+}
+
+#[test]
+fn agent_dtos() {
+    assert_round_trip(
+        AgentCreatedDto {
+            id: Uuid::nil(),
+            token: Uuid::max(),
+        },
+        json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "token": "ffffffff-ffff-ffff-ffff-ffffffffffff",
+        }),
+    );
+}
+
+#[test]
+fn check_dtos() {
+    assert_round_trip(
+        CheckTaskRequestDto {
+            solution: "3".into(),
+            code: None,
+        },
+        json!({ "solution": "3", "code": null }),
+    );
+    assert_round_trip(
+        CheckTaskResponseDto::new(false),
+        json!({ "correct": false }),
+    );
+}
+
+#[test]
+fn code_submission_dtos() {
+    assert_round_trip(
+        CodeSubmissionResponseDto {
+            compile_error: Some("error".into()),
+            diagnostics: Some(vec![DiagnosticDto {
+                level: "error".into(),
+                code: Some("E0308".into()),
+                message: "mismatched types".into(),
+                file: Some("src/main.rs".into()),
+                line: Some(5),
+                column: Some(18),
+                label: None,
+                notes: vec![],
+                suggestions: vec![SuggestionDto {
+                    message: "try".into(),
+                    line: 5,
+                    column: 18,
+                    replacement: "1".into(),
+                }],
+            }]),
+            ..Default::default()
+        },
+        json!({
+            "correct": false,
+            "compile_error": "error",
+            "diagnostics": [{
+                "level": "error",
+                "code": "E0308",
+                "message": "mismatched types",
+                "file": "src/main.rs",
+                "line": 5,
+                "column": 18,
+                "label": null,
+                "notes": [],
+                "suggestions": [{ "message": "try", "line": 5, "column": 18, "replacement": "1" }],
+            }],
+            "timed_out": false,
+            "exit_code": null,
+            "output": null,
+            "stderr": null,
+            "wall_time_ms": null,
+            "cpu_time_ms": null,
+        }),
+    );
+}
+
+#[test]
+fn regrade_dtos() {
+    assert_round_trip(
+        RegradeReportDto {
+            task_id: Uuid::nil(),
+            changes: vec![RegradeAgentDto {
+                agent_id: Uuid::nil(),
+                change: RegradeChangeDto::Revoked,
+                time_in_ms: None,
+            }],
+            unchanged: 2,
+        },
+        json!({
+            "task_id": "00000000-0000-0000-0000-000000000000",
+            "changes": [{
+                "agent_id": "00000000-0000-0000-0000-000000000000",
+                "change": "Revoked",
+                "time_in_ms": null,
+            }],
+            "unchanged": 2,
+        }),
+    );
+}
+
+#[test]
+fn bundle_dtos() {
+    let (task_type, task_type_json) = task_types().remove(0);
+    let start_time = Utc.with_ymd_and_hms(2024, 12, 1, 5, 0, 0).unwrap();
+    assert_round_trip(
+        BundleDto {
+            version: 1,
+            tasks: vec![BundleTaskDto {
+                id: Uuid::nil(),
+                task: TaskCreateDto {
+                    name: "Hello".into(),
+                    task_type,
+                    solution: "hello".into(),
+                },
+                archived_at: None,
+                aoc_year: None,
+                aoc_day: None,
+                aoc_part: None,
+            }],
+            agents: None,
+            completions: Some(vec![BundleCompletionDto {
+                id: Uuid::nil(),
+                task_id: Uuid::nil(),
+                agent_id: Uuid::nil(),
+                start_time,
+                completion_time: None,
+                best_time_in_ms: None,
+                best_run_time_in_ms: Some(3),
+            }]),
+        },
+        json!({
+            "version": 1,
+            "tasks": [{
+                "id": "00000000-0000-0000-0000-000000000000",
+                "name": "Hello",
+                "taskType": task_type_json,
+                "solution": "hello",
+                "archived_at": null,
+                "aoc_year": null,
+                "aoc_day": null,
+                "aoc_part": null,
+            }],
+            "completions": [{
+                "id": "00000000-0000-0000-0000-000000000000",
+                "task_id": "00000000-0000-0000-0000-000000000000",
+                "agent_id": "00000000-0000-0000-0000-000000000000",
+                "start_time": "2024-12-01T05:00:00Z",
+                "completion_time": null,
+                "best_time_in_ms": null,
+                "best_run_time_in_ms": 3,
+            }],
+        }),
+    );
+}
+
+#[test]
+fn llm_usage_dtos() {
+    let usage = LlmUsageCountsDto {
+        requests: 2,
+        prompt_tokens: 300,
+        completion_tokens: 50,
+        latency_ms: 1200,
+    };
+    assert_round_trip(
+        LlmUsageDto {
+            agent_id: Uuid::nil(),
+            token_budget: Some(100000),
+            total: usage.clone(),
+            tasks: vec![LlmTaskUsageDto {
+                task_id: None,
+                usage,
+            }],
+        },
+        json!({
+            "agent_id": "00000000-0000-0000-0000-000000000000",
+            "token_budget": 100000,
+            "total": {
+                "requests": 2,
+                "prompt_tokens": 300,
+                "completion_tokens": 50,
+                "latency_ms": 1200,
+            },
+            "tasks": [{
+                "task_id": null,
+                "requests": 2,
+                "prompt_tokens": 300,
+                "completion_tokens": 50,
+                "latency_ms": 1200,
+            }],
+        }),
+    );
+}
@@ -0,0 +1,138 @@
+use crate::markdown::parse;
+
+/// Phrases that introduce the answer, the more specific ones first.
+const MARKERS: [&str; 7] = [
+    "final answer",
+    "the answer is",
+    "answer:",
+    "the solution is",
+    "solution:",
+    "the result is",
+    "result:",
+];
+
+/// The final answer of a response to a task that is answered directly, from the text outside the
+/// code blocks. In this order:
+/// - the last `\boxed{..}`
+/// - the last line with a phrase like "final answer" or "the answer is", e.g. "**Final Answer:**
+///   The total is `42`." or "The answer is **42**." (or the next line, if nothing follows the
+///   phrase)
+/// - the last inline code, e.g. `42`, as the prompt asks for
+pub fn answer(response: &str) -> Option<String> {
+    let (_, prose) = parse(response);
+    let answer = boxed(&prose)
+        .or_else(|| marked(&prose))
+        .or_else(|| prose.iter().rev().find_map(|line| code_spans(line).pop()))?;
+    Some(clean(answer)).filter(|answer| !answer.is_empty())
+}
+
+fn boxed<'a>(prose: &[&'a str]) -> Option<&'a str> {
+    prose.iter().rev().find_map(|line| {
+        let start = line.rfind("\\boxed{")? + "\\boxed{".len();
+        let mut depth = 1;
+        for (index, c) in line[start..].char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return Some(&line[start..start + index]);
+            }
+        }
+        None
+    })
+}
+
+fn marked<'a>(prose: &[&'a str]) -> Option<&'a str> {
+    for (index, line) in prose.iter().enumerate().rev() {
+        let lower = line.to_ascii_lowercase();
+        let Some(end) = MARKERS
+            .iter()
+            .find_map(|marker| Some(lower.rfind(marker)? + marker.len()))
+        else {
+            continue;
+        };
+
+        let rest = trim_marker(&line[end..]);
+        if !rest.is_empty() {
+            return Some(rest);
+        }
+        // "**Final answer:**" on a line of its own.
+        return prose[index + 1..]
+            .iter()
+            .map(|line| line.trim())
+            .find(|line| !line.is_empty());
+    }
+    None
+}
+
+/// Removes what is left of the phrase, like the ":" and "**" of "**Final answer:** 42" or the
+/// "is" of "The final answer is 42".
+fn trim_marker(rest: &str) -> &str {
+    let rest = trim_emphasis(rest.trim_start_matches([' ', ':']));
+    let rest = match rest.get(..3) {
+        Some(is) if is.eq_ignore_ascii_case("is ") || is.eq_ignore_ascii_case("is:") => &rest[3..],
+        _ => rest,
+    };
+    trim_emphasis(rest.trim_start_matches([' ', ':', '#'])).trim_end()
+}
+
+/// Removes the "**" that closes a bold phrase, but not the one that opens a bold answer.
+fn trim_emphasis(text: &str) -> &str {
+    if text.starts_with("**") && text.matches("**").count() % 2 == 1 {
+        text[2..].trim_start_matches([' ', ':'])
+    } else {
+        text
+    }
+}
+
+/// The inline code of a line, e.g. `a` and `` b ``.
+fn code_spans(line: &str) -> Vec<&str> {
+    let mut spans = vec![];
+    let mut rest = line;
+    while let Some(start) = rest.find('`') {
+        let ticks = rest[start..].chars().take_while(|c| *c == '`').count();
+        let after = &rest[start + ticks..];
+        let Some(end) = find_run(after, ticks) else {
+            break;
+        };
+        spans.push(after[..end].trim());
+        rest = &after[end + ticks..];
+    }
+    spans
+}
+
+/// The start of the first run of exactly `len` backticks.
+fn find_run(text: &str, len: usize) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find('`') {
+        let start = offset + start;
+        let run = text[start..].chars().take_while(|c| *c == '`').count();
+        if run == len {
+            return Some(start);
+        }
+        offset = start + run;
+    }
+    None
+}
+
+/// The answer without the Markdown around it: the inline code or bold text if there is some,
+/// without trailing punctuation.
+fn clean(answer: &str) -> String {
+    let answer = code_spans(answer)
+        .first()
+        .copied()
+        .or_else(|| bold(answer))
+        .unwrap_or(answer);
+    answer
+        .trim_start_matches(|c: char| c.is_whitespace() || "*_$\"".contains(c))
+        .trim_end_matches(|c: char| c.is_whitespace() || "*_$\".,;!".contains(c))
+        .to_string()
+}
+
+fn bold(text: &str) -> Option<&str> {
+    let start = text.find("**")? + 2;
+    let end = text[start..].find("**")? + start;
+    Some(&text[start..end])
+}
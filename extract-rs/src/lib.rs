@@ -0,0 +1,38 @@
+//! Extracts the code and the final answer from the response of an LLM.
+//!
+//! Responses are Markdown, but rarely clean: several code blocks (snippets, a draft and a fixed
+//! version, the command to run it), blocks nested in a ```markdown block, fences indented in
+//! lists, a closing fence cut off by the token limit, answers in bold or `\boxed{}`.
+//!
+//! ```
+//! let response = "Read the input:\n```rust\nlet input = parse();\n```\n\nThe program:\n```rust\n\
+//!     fn main() {\n    let input = std::fs::read_to_string(\"input.txt\").unwrap();\n}\n```\n\
+//!     Run it with:\n```sh\ncargo run\n```\n\nThe answer is **42**.";
+//!
+//! assert_eq!(extract::code_blocks(response).len(), 3);
+//! assert!(extract::solution_code(response).unwrap().starts_with("fn main() {"));
+//! assert_eq!(extract::answer(response).as_deref(), Some("42"));
+//! ```
+
+mod answer;
+mod markdown;
+
+pub use answer::answer;
+pub use markdown::{code_blocks, CodeBlock};
+
+/// The file the solutions read their input from, see the `solver-rs` template.
+const INPUT_FILE: &str = "input.txt";
+
+/// The code of the block most likely to be a complete solution in the shape of the `solver-rs`
+/// template: Rust (or untagged) code with a `fn main`. Blocks that read `input.txt` come first,
+/// then blocks with a closing fence; of equally good blocks the last one wins, as models tend to
+/// end with the fixed version.
+pub fn solution_code(response: &str) -> Option<String> {
+    code_blocks(response)
+        .into_iter()
+        .filter(|block| block.language.is_none() || block.is_rust())
+        .filter(|block| block.code.contains("fn main"))
+        .filter(|block| code_blocks(&block.code).is_empty())
+        .max_by_key(|block| (block.code.contains(INPUT_FILE), !block.unclosed))
+        .map(|block| block.code)
+}
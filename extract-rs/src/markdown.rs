@@ -0,0 +1,108 @@
+/// A fenced code block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// First word of the info string in lower case, e.g. `rust` for "```Rust,ignore".
+    pub language: Option<String>,
+    /// The lines between the fences, without the indentation of the opening fence.
+    pub code: String,
+    /// The closing fence is missing, e.g. because the response was cut off.
+    pub unclosed: bool,
+}
+
+impl CodeBlock {
+    pub fn is_rust(&self) -> bool {
+        matches!(self.language.as_deref(), Some("rust" | "rs"))
+    }
+}
+
+struct Fence {
+    indent: usize,
+    char: char,
+    len: usize,
+    info: String,
+}
+
+/// All fenced code blocks (``` or ~~~) in the order they start. Blocks inside blocks are included
+/// after the block around them, so a ```rust block in a ```markdown block is found, even if the
+/// model closed the outer block with the fence of the inner one.
+///
+/// The fences may be indented by any amount, e.g. in lists. A block without closing fence runs to
+/// the end of the text.
+pub fn code_blocks(text: &str) -> Vec<CodeBlock> {
+    let mut blocks = vec![];
+    for block in parse(text).0 {
+        let nested = code_blocks(&block.code);
+        blocks.push(block);
+        blocks.extend(nested);
+    }
+    blocks
+}
+
+/// The top-level code blocks and the lines outside of them.
+pub(crate) fn parse(text: &str) -> (Vec<CodeBlock>, Vec<&str>) {
+    let mut blocks = vec![];
+    let mut prose = vec![];
+    let mut lines = text.lines();
+    while let Some(line) = lines.next() {
+        let Some(fence) = opening_fence(line) else {
+            prose.push(line);
+            continue;
+        };
+
+        let mut code = vec![];
+        let mut unclosed = true;
+        for line in lines.by_ref() {
+            if is_closing_fence(line, &fence) {
+                unclosed = false;
+                break;
+            }
+            code.push(strip_indent(line, fence.indent));
+        }
+        blocks.push(CodeBlock {
+            language: language(&fence.info),
+            code: code.join("\n"),
+            unclosed,
+        });
+    }
+    (blocks, prose)
+}
+
+fn opening_fence(line: &str) -> Option<Fence> {
+    let trimmed = line.trim_start();
+    let char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == char).count();
+    let info = trimmed[len..].trim();
+    // "```a` and `b```" is inline code, not a fence.
+    if len < 3 || (char == '`' && info.contains('`')) {
+        return None;
+    }
+    Some(Fence {
+        indent: leading_spaces(line),
+        char,
+        len,
+        info: info.to_string(),
+    })
+}
+
+fn is_closing_fence(line: &str, fence: &Fence) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= fence.len && trimmed.chars().all(|c| c == fence.char)
+}
+
+/// Only ASCII spaces are indentation, other whitespace (e.g. a no-break space) is content.
+fn strip_indent(line: &str, indent: usize) -> &str {
+    &line[leading_spaces(line).min(indent)..]
+}
+
+fn leading_spaces(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// "rust" for "rust", "Rust", "rust,ignore", "{.rust}" and "rust title=main.rs".
+fn language(info: &str) -> Option<String> {
+    let info = info.trim_start_matches(['{', '.']);
+    let end = info
+        .find(|c: char| !(c.is_alphanumeric() || "+-_#".contains(c)))
+        .unwrap_or(info.len());
+    Some(info[..end].to_lowercase()).filter(|language| !language.is_empty())
+}
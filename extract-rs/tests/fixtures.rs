@@ -0,0 +1,62 @@
+//! Runs every response `tests/fixtures/<name>.md` through the extraction:
+//! - `solution_code` must return the contents of `<name>.rs`, or nothing if there is no such file
+//! - `answer` must return the contents of `<name>.answer` (nothing if it is empty), if it exists
+
+use std::{fs, path::Path};
+
+#[test]
+fn fixtures() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut responses: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "md"))
+        .collect();
+    responses.sort();
+    assert!(!responses.is_empty());
+
+    let mut failures = vec![];
+    for path in responses {
+        let name = path.file_stem().unwrap().to_string_lossy();
+        let response = fs::read_to_string(&path).unwrap();
+
+        let expected_code = fs::read_to_string(path.with_extension("rs")).ok();
+        let code = extract::solution_code(&response);
+        if code.as_deref().map(str::trim_end) != expected_code.as_deref().map(str::trim_end) {
+            failures.push(format!("{name}: code {code:#?}"));
+        }
+
+        if let Ok(expected_answer) = fs::read_to_string(path.with_extension("answer")) {
+            let expected_answer = Some(expected_answer.trim()).filter(|answer| !answer.is_empty());
+            let answer = extract::answer(&response);
+            if answer.as_deref() != expected_answer {
+                failures.push(format!("{name}: answer {answer:?}"));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn code_blocks() {
+    let response = "````markdown\n```rust\nfn main() {}\n```\n````\n\n  ~~~ Python\n  print(1)\n";
+    let blocks = extract::code_blocks(response);
+
+    let languages: Vec<_> = blocks
+        .iter()
+        .map(|block| block.language.as_deref())
+        .collect();
+    assert_eq!(languages, [Some("markdown"), Some("rust"), Some("python")]);
+    assert_eq!(blocks[1].code, "fn main() {}");
+    assert!(blocks[1].is_rust());
+    assert_eq!(blocks[2].code, "print(1)");
+    assert!(blocks[2].unclosed);
+}
+
+#[test]
+fn code_blocks_with_no_break_space() {
+    let blocks = extract::code_blocks(" ```rust\n\u{a0}fn main() {}\n ```\n");
+    assert_eq!(blocks.len(), 1);
+    assert_eq!(blocks[0].code, "\u{a0}fn main() {}");
+    assert!(!blocks[0].unclosed);
+}
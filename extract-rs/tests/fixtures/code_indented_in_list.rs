@@ -0,0 +1,17 @@
+use std::fs;
+
+fn main() {
+    let input_string = fs::read_to_string("input.txt").unwrap();
+    let mut left: Vec<i32> = vec![];
+    let mut right: Vec<i32> = vec![];
+    for line in input_string.lines() {
+        let mut numbers = line.split_whitespace().map(|n| n.parse::<i32>().unwrap());
+        left.push(numbers.next().unwrap());
+        right.push(numbers.next().unwrap());
+    }
+    left.sort();
+    right.sort();
+
+    let result: i32 = left.iter().zip(&right).map(|(a, b)| (a - b).abs()).sum();
+    println!("{result}");
+}
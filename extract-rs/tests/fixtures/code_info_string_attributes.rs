@@ -0,0 +1,7 @@
+use std::fs;
+
+fn main() {
+    let input_string = fs::read_to_string("input.txt").unwrap();
+    let result = input_string.split(',').count();
+    println!("{result}");
+}
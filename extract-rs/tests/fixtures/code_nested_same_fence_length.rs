@@ -0,0 +1,8 @@
+use std::fs;
+
+fn main() {
+    let input_string = fs::read_to_string("input.txt").unwrap();
+    let result = input_string.matches("XMAS").count();
+
+    println!("{result}");
+}
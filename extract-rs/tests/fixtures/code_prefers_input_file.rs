@@ -0,0 +1,14 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+fn main() {
+    let file = File::open("input.txt").unwrap();
+    let mut result = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line.unwrap();
+        if line.starts_with('#') {
+            result += 1;
+        }
+    }
+    println!("{result}");
+}
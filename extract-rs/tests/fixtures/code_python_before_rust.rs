@@ -0,0 +1,7 @@
+use std::fs;
+
+fn main() {
+    let input_string = fs::read_to_string("input.txt").unwrap();
+    let result: i64 = input_string.lines().map(|line| line.parse::<i64>().unwrap()).sum();
+    println!("{result}");
+}
@@ -0,0 +1,16 @@
+use std::fs;
+
+fn main() {
+    let input_string = fs::read_to_string("input.txt").unwrap();
+    let mut total = 0;
+
+    for line in input_string.lines() {
+        let digits: Vec<u32> = line.chars().filter_map(|c| c.to_digit(10)).collect();
+        if let (Some(first), Some(last)) = (digits.first(), digits.last()) {
+            total += first * 10 + last;
+        }
+    }
+
+    let result = total;
+    println!("{result}");
+}
@@ -0,0 +1,20 @@
+use std::fs;
+
+fn parse_line(line: &str) -> Vec<i64> {
+    line.split_whitespace().map(|n| n.parse().unwrap()).collect()
+}
+
+fn next_value(values: &[i64]) -> i64 {
+    if values.iter().all(|&v| v == 0) {
+        return 0;
+    }
+    let differences: Vec<i64> = values.windows(2).map(|w| w[1] - w[0]).collect();
+    values.last().unwrap() + next_value(&differences)
+}
+
+fn main() {
+    let input_string = fs::read_to_string("input.txt").unwrap();
+    let result: i64 = input_string.lines().map(|line| next_value(&parse_line(line))).sum();
+
+    println!("{result}");
+}
@@ -0,0 +1,14 @@
+use std::fs;
+
+fn main() {
+    let input_string = fs::read_to_string("input.txt").unwrap();
+    let mut floor = 0;
+    for c in input_string.chars() {
+        match c {
+            '(' => floor += 1,
+            ')' => floor -= 1,
+            _ => {}
+        }
+    }
+    println!("{floor}");
+}
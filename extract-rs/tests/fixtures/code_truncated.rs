@@ -0,0 +1,9 @@
+use std::fs;
+
+fn main() {
+    let input_string = fs::read_to_string("input.txt").unwrap();
+    let grid: Vec<Vec<char>> = input_string.lines().map(|l| l.chars().collect()).collect();
+    let mut result = 0;
+    for (y, row) in grid.iter().enumerate() {
+        for (x, c) in row.iter().enumerate() {
+            if *c == 'A' && y > 0 && x > 0
@@ -0,0 +1,219 @@
+use std::{
+    fs,
+    net::SocketAddr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use anyhow::{bail, Context};
+use regex::Regex;
+use serde::Deserialize;
+
+/// The contents of the fixture file, see `mock-llm.example.toml`.
+#[derive(Debug, Deserialize)]
+pub struct Fixture {
+    #[serde(default = "default_address")]
+    pub address: SocketAddr,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Answers the prompts no rule matches. Without it they get a 404.
+    pub default: Option<Rule>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Rule {
+    /// Regex the prompt must match: the `prompt` (and `system`) of `/api/generate`, or the
+    /// contents of all messages of the chat APIs, one per line.
+    #[serde(with = "serde_regex", default = "match_all")]
+    pub pattern: Regex,
+    /// Only requests for this model match.
+    pub model: Option<String>,
+    /// The answer of the model.
+    pub response: Option<String>,
+    /// Answers served in turn instead of `response`, the last one again once all were served.
+    #[serde(default)]
+    pub responses: Vec<String>,
+    /// Waits before answering, e.g. to test timeouts.
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// Fails with this HTTP status instead, with the response as error message.
+    pub status: Option<u16>,
+    #[serde(skip)]
+    served: AtomicUsize,
+}
+
+fn default_address() -> SocketAddr {
+    ([127, 0, 0, 1], 11434).into()
+}
+
+fn match_all() -> Regex {
+    Regex::new("").unwrap()
+}
+
+mod serde_regex {
+    use regex::Regex;
+    use serde::{de::Error, Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern).map_err(D::Error::custom)
+    }
+}
+
+impl Fixture {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("cannot read {path}"))?;
+        let fixture: Self =
+            toml::from_str(&text).with_context(|| format!("invalid fixture {path}"))?;
+        fixture
+            .validate()
+            .with_context(|| format!("invalid fixture {path}"))?;
+        Ok(fixture)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        let rules = self
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| (format!("rule {index}"), rule));
+        let default = self
+            .default
+            .iter()
+            .map(|rule| ("default".to_string(), rule));
+        for (label, rule) in rules.chain(default) {
+            if rule.response.is_some() != rule.responses.is_empty() {
+                bail!("{label}: either `response` or `responses` must be set");
+            }
+        }
+        Ok(())
+    }
+
+    /// The first rule for the model that matches the prompt, or the default one. Returns the
+    /// index of the rule for the log (`None` for the default).
+    pub fn find(&self, model: &str, prompt: &str) -> Option<(Option<usize>, &Rule)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| {
+                rule.model
+                    .as_ref()
+                    .map_or(true, |rule_model| rule_model == model)
+                    && rule.pattern.is_match(prompt)
+            })
+            .map(|(index, rule)| (Some(index), rule))
+            .or_else(|| Some((None, self.default.as_ref()?)))
+    }
+}
+
+impl Rule {
+    /// The next response of the rule.
+    pub fn next_response(&self) -> String {
+        if let Some(response) = &self.response {
+            return response.clone();
+        }
+        let served = self.served.fetch_add(1, Ordering::Relaxed);
+        self.responses[served.min(self.responses.len() - 1)].clone()
+    }
+
+    pub fn latency(&self) -> Duration {
+        Duration::from_millis(self.latency_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(toml: &str) -> Fixture {
+        let fixture: Fixture = toml::from_str(toml).unwrap();
+        fixture.validate().unwrap();
+        fixture
+    }
+
+    fn invalid(toml: &str) -> String {
+        let fixture: Fixture = toml::from_str(toml).unwrap();
+        fixture.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn finds_the_first_matching_rule_for_the_model() {
+        let fixture = fixture(
+            r#"
+            [[rules]]
+            pattern = "two sum"
+            model = "large"
+            response = "large two sum"
+
+            [[rules]]
+            pattern = "(?i)two"
+            response = "any two"
+
+            [default]
+            response = "default"
+            "#,
+        );
+
+        let (index, rule) = fixture.find("large", "solve two sum").unwrap();
+        assert_eq!(
+            (index, rule.next_response().as_str()),
+            (Some(0), "large two sum")
+        );
+        let (index, rule) = fixture.find("small", "solve two sum").unwrap();
+        assert_eq!((index, rule.next_response().as_str()), (Some(1), "any two"));
+        let (index, rule) = fixture.find("small", "Two").unwrap();
+        assert_eq!((index, rule.next_response().as_str()), (Some(1), "any two"));
+        let (index, rule) = fixture.find("small", "three").unwrap();
+        assert_eq!((index, rule.next_response().as_str()), (None, "default"));
+    }
+
+    #[test]
+    fn finds_nothing_without_default() {
+        let fixture = fixture(
+            r#"
+            [[rules]]
+            pattern = "two"
+            response = "two"
+            "#,
+        );
+        assert!(fixture.find("small", "three").is_none());
+    }
+
+    #[test]
+    fn serves_responses_in_turn_then_the_last_one() {
+        let fixture = fixture(
+            r#"
+            [[rules]]
+            responses = ["first", "second"]
+            "#,
+        );
+        let (_, rule) = fixture.find("small", "anything").unwrap();
+        let responses: Vec<_> = (0..4).map(|_| rule.next_response()).collect();
+        assert_eq!(responses, ["first", "second", "second", "second"]);
+    }
+
+    #[test]
+    fn rules_need_exactly_one_kind_of_response() {
+        let both = r#"
+            [[rules]]
+            response = "one"
+            responses = ["two"]
+            "#;
+        assert_eq!(
+            invalid(both),
+            "rule 0: either `response` or `responses` must be set"
+        );
+
+        let default_without = r#"
+            [[rules]]
+            response = "one"
+
+            [default]
+            latency_ms = 10
+            "#;
+        assert_eq!(
+            invalid(default_without),
+            "default: either `response` or `responses` must be set"
+        );
+    }
+}
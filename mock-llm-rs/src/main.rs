@@ -0,0 +1,30 @@
+mod fixture;
+mod ollama;
+mod openai;
+mod reply;
+
+use std::{env, sync::Arc};
+
+use axum::{routing::post, Router};
+use fixture::Fixture;
+use tokio::net::TcpListener;
+
+const DEFAULT_FIXTURE: &str = "mock-llm.toml";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let path = env::args().nth(1).unwrap_or(DEFAULT_FIXTURE.into());
+    let fixture = Fixture::load(&path)?;
+    let address = fixture.address;
+
+    let router = Router::new()
+        .route("/api/generate", post(ollama::generate))
+        .route("/api/chat", post(ollama::chat))
+        .route("/v1/chat/completions", post(openai::chat_completions))
+        .with_state(Arc::new(fixture));
+
+    println!("Serving {path} on {address}");
+    let listener = TcpListener::bind(address).await?;
+    axum::serve(listener, router).await?;
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header::CONTENT_TYPE, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+use crate::{
+    fixture::Fixture,
+    reply::{chat_prompt, reply, Message, Reply},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct GenerateRequest {
+    model: String,
+    #[serde(default)]
+    prompt: String,
+    system: Option<String>,
+    stream: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatRequest {
+    model: String,
+    messages: Vec<Message>,
+    stream: Option<bool>,
+}
+
+/// `POST /api/generate`, as called by `simple-bot-py`.
+pub async fn generate(State(fixture): State<Arc<Fixture>>, body: Bytes) -> Response {
+    let request: GenerateRequest = match parse(&body) {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+    let prompt = match &request.system {
+        Some(system) => format!("{system}\n{}", request.prompt),
+        None => request.prompt.clone(),
+    };
+    match reply(&fixture, "/api/generate", &request.model, &prompt).await {
+        Ok(reply) => {
+            let mut body = response_body(&request.model, &reply);
+            body["response"] = reply.content.into();
+            respond(body, request.stream)
+        }
+        Err(error) => error_response(error),
+    }
+}
+
+/// `POST /api/chat`, as called by `agent-rs`.
+pub async fn chat(State(fixture): State<Arc<Fixture>>, body: Bytes) -> Response {
+    let request: ChatRequest = match parse(&body) {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+    let prompt = chat_prompt(&request.messages);
+    match reply(&fixture, "/api/chat", &request.model, &prompt).await {
+        Ok(reply) => {
+            let mut body = response_body(&request.model, &reply);
+            body["message"] = json!({ "role": "assistant", "content": reply.content });
+            respond(body, request.stream)
+        }
+        Err(error) => error_response(error),
+    }
+}
+
+/// Ollama reads the body as JSON whatever the content type, e.g. for `curl -d`.
+fn parse<T: DeserializeOwned>(body: &[u8]) -> Result<T, Response> {
+    serde_json::from_slice(body)
+        .map_err(|error| error_response((StatusCode::BAD_REQUEST, error.to_string())))
+}
+
+fn response_body(model: &str, reply: &Reply) -> Value {
+    json!({
+        "model": model,
+        "created_at": Utc::now().to_rfc3339(),
+        "done": true,
+        "done_reason": "stop",
+        "prompt_eval_count": reply.prompt_tokens,
+        "eval_count": reply.completion_tokens,
+    })
+}
+
+/// Ollama streams unless `stream` is false. The stream is a single line with the whole answer,
+/// which is a valid stream.
+fn respond(body: Value, stream: Option<bool>) -> Response {
+    if stream == Some(false) {
+        return Json(body).into_response();
+    }
+    (
+        [(CONTENT_TYPE, "application/x-ndjson")],
+        format!("{body}\n"),
+    )
+        .into_response()
+}
+
+fn error_response((status, message): (StatusCode, String)) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
@@ -0,0 +1,68 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    fixture::Fixture,
+    reply::{chat_prompt, reply, Message},
+};
+
+static COMPLETIONS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Deserialize)]
+pub struct ChatCompletionRequest {
+    model: String,
+    messages: Vec<Message>,
+    #[serde(default)]
+    stream: bool,
+}
+
+/// `POST /v1/chat/completions`. The API key is not checked.
+pub async fn chat_completions(
+    State(fixture): State<Arc<Fixture>>,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Response {
+    if request.stream {
+        return error_response((StatusCode::BAD_REQUEST, "streaming is not supported".into()));
+    }
+
+    let prompt = chat_prompt(&request.messages);
+    let reply = match reply(&fixture, "/v1/chat/completions", &request.model, &prompt).await {
+        Ok(reply) => reply,
+        Err(error) => return error_response(error),
+    };
+    let id = COMPLETIONS.fetch_add(1, Ordering::Relaxed);
+    Json(json!({
+        "id": format!("chatcmpl-mock-{id}"),
+        "object": "chat.completion",
+        "created": Utc::now().timestamp(),
+        "model": request.model,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": reply.content },
+            "finish_reason": "stop",
+        }],
+        "usage": {
+            "prompt_tokens": reply.prompt_tokens,
+            "completion_tokens": reply.completion_tokens,
+            "total_tokens": reply.prompt_tokens + reply.completion_tokens,
+        },
+    }))
+    .into_response()
+}
+
+fn error_response((status, message): (StatusCode, String)) -> Response {
+    let body = json!({ "error": { "message": message, "type": "mock_error" } });
+    (status, Json(body)).into_response()
+}
@@ -0,0 +1,62 @@
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::fixture::Fixture;
+
+/// A chat message of the Ollama and the OpenAI chat APIs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub role: String,
+    pub content: String,
+}
+
+/// The answer to a prompt, with token counts that roughly look like the real ones.
+pub struct Reply {
+    pub content: String,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+/// The answer of the first matching rule, after its latency. Fails with the status of the rule,
+/// or 404 if no rule matches.
+pub async fn reply(
+    fixture: &Fixture,
+    endpoint: &str,
+    model: &str,
+    prompt: &str,
+) -> Result<Reply, (StatusCode, String)> {
+    let Some((index, rule)) = fixture.find(model, prompt) else {
+        println!("{endpoint} {model}: no rule matches");
+        return Err((StatusCode::NOT_FOUND, "no rule matches the prompt".into()));
+    };
+    match index {
+        Some(index) => println!("{endpoint} {model}: rule {index}"),
+        None => println!("{endpoint} {model}: default"),
+    }
+
+    tokio::time::sleep(rule.latency()).await;
+    let content = rule.next_response();
+    if let Some(status) = rule.status {
+        let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        return Err((status, content));
+    }
+    Ok(Reply {
+        prompt_tokens: tokens(prompt),
+        completion_tokens: tokens(&content),
+        content,
+    })
+}
+
+/// The prompt the rules are matched against: the contents of the messages, one per line.
+pub fn chat_prompt(messages: &[Message]) -> String {
+    messages
+        .iter()
+        .map(|message| message.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Counts words instead of tokens, close enough for tests of the usage accounting.
+fn tokens(text: &str) -> u64 {
+    text.split_whitespace().count() as u64
+}
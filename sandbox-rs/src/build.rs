@@ -0,0 +1,527 @@
+use std::{
+    env,
+    ffi::OsStr,
+    fmt, fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    sys::{self, FileAccess},
+    unique_temp_dir, Limits,
+};
+
+/// The only environment variables builds get, so the toolchains are found. Everything else of the
+/// caller (e.g. API keys of the backend) is hidden from build scripts and proc macros. `TMPDIR` is
+/// set to a fresh directory of the build.
+const BUILD_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "CARGO_HOME",
+    "RUSTUP_HOME",
+    "RUSTUP_TOOLCHAIN",
+];
+
+/// What cargo needs of `CARGO_HOME`: not all of it, it may hold registry tokens.
+const CARGO_HOME_ENTRIES: &[&str] = &["bin", "registry", "git", "config.toml", "config"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Build {
+    pub success: bool,
+    pub timed_out: bool,
+    /// The compiled binary, if the build succeeded.
+    pub executable: Option<PathBuf>,
+    /// Errors and warnings of the compiler, in the order they were reported.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The diagnostics as rustc prints them.
+    pub rendered: String,
+    /// Output of cargo itself, e.g. when the manifest cannot be read.
+    pub stderr: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// `error` or `warning`.
+    pub level: String,
+    /// Error code like `E0308`, if any.
+    pub code: Option<String>,
+    pub message: String,
+    /// Location of the primary span.
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// Label of the primary span, e.g. "expected `i32`, found `&str`".
+    pub label: Option<String>,
+    /// Notes and helps attached to the diagnostic.
+    pub notes: Vec<String>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+    pub replacement: String,
+}
+
+impl Build {
+    /// Only the errors, without the warnings.
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.level == "error")
+    }
+}
+
+/// One line per diagnostic plus one per note and suggestion, e.g.
+/// `src/main.rs:5:18: error[E0308]: mismatched types (expected `i32`, found `&str`)`.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let (Some(file), Some(line), Some(column)) = (&self.file, self.line, self.column) {
+            write!(f, "{file}:{line}:{column}: ")?;
+        }
+        write!(f, "{}", self.level)?;
+        if let Some(code) = &self.code {
+            write!(f, "[{code}]")?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(label) = &self.label {
+            write!(f, " ({label})")?;
+        }
+        for note in &self.notes {
+            write!(f, "\n  {note}")?;
+        }
+        for suggestion in &self.suggestions {
+            write!(
+                f,
+                "\n  suggestion at {}:{}: {}",
+                suggestion.line, suggestion.column, suggestion.message
+            )?;
+            if !suggestion.replacement.is_empty() {
+                write!(f, " `{}`", suggestion.replacement)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "reason")]
+enum CargoMessage {
+    #[serde(rename = "compiler-message")]
+    CompilerMessage { message: RustcDiagnostic },
+    #[serde(rename = "compiler-artifact")]
+    CompilerArtifact { executable: Option<PathBuf> },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize)]
+struct RustcDiagnostic {
+    message: String,
+    code: Option<RustcCode>,
+    level: String,
+    spans: Vec<RustcSpan>,
+    children: Vec<RustcDiagnostic>,
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: usize,
+    column_start: usize,
+    is_primary: bool,
+    label: Option<String>,
+    suggested_replacement: Option<String>,
+}
+
+/// Runs `cargo build --release --message-format=json --offline` in `crate_dir`, confined like a
+/// run (see `build_limits`). The build is killed if it takes longer than `timeout`.
+pub fn build(crate_dir: &Path, timeout: Duration) -> io::Result<Build> {
+    build_with_target_dir(crate_dir, None, timeout)
+}
+
+/// Like `build`, but with another target directory, e.g. one shared by several crates so common
+/// dependencies are only compiled once.
+pub fn build_with_target_dir(
+    crate_dir: &Path,
+    target_dir: Option<&Path>,
+    timeout: Duration,
+) -> io::Result<Build> {
+    let mut command = command_with_build_env("cargo");
+    if let Some(target_dir) = target_dir {
+        command.env("CARGO_TARGET_DIR", target_dir);
+    }
+    command
+        .args([
+            "build",
+            "--release",
+            "--message-format=json",
+            "--offline",
+            "--quiet",
+            "--color",
+            "never",
+        ])
+        .current_dir(crate_dir);
+    let access = FileAccess {
+        read: toolchain_paths(crate_dir),
+        write: [Some(crate_dir), target_dir]
+            .into_iter()
+            .flatten()
+            .map(Path::to_path_buf)
+            .collect(),
+    };
+    let output = run_with_timeout(&mut command, access, timeout)?;
+
+    let mut build = Build {
+        success: output.success,
+        timed_out: output.timed_out,
+        executable: None,
+        diagnostics: vec![],
+        rendered: String::new(),
+        stderr: output.stderr,
+    };
+    // Build scripts may print lines that are not JSON.
+    let messages = output
+        .stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok());
+    for message in messages {
+        match message {
+            CargoMessage::CompilerMessage { message } => {
+                if let Some(rendered) = &message.rendered {
+                    build.rendered.push_str(rendered);
+                }
+                if let Some(diagnostic) = to_diagnostic(message) {
+                    build.diagnostics.push(diagnostic);
+                }
+            }
+            CargoMessage::CompilerArtifact {
+                executable: Some(executable),
+            } => build.executable = Some(executable),
+            _ => {}
+        }
+    }
+    if !build.success {
+        build.executable = None;
+    }
+
+    Ok(build)
+}
+
+/// Runs any other build command (e.g. a compiler or a syntax check) in `dir`. There are no
+/// structured diagnostics, `rendered` is what the command printed.
+pub fn build_command(
+    dir: &Path,
+    program: &Path,
+    args: &[String],
+    timeout: Duration,
+) -> io::Result<Build> {
+    let mut command = command_with_build_env(program);
+    command.args(args).current_dir(dir);
+    let access = FileAccess {
+        read: vec![program.to_path_buf()],
+        write: vec![dir.to_path_buf()],
+    };
+    let output = run_with_timeout(&mut command, access, timeout)?;
+
+    Ok(Build {
+        success: output.success,
+        timed_out: output.timed_out,
+        executable: None,
+        diagnostics: vec![],
+        rendered: output.stdout + &output.stderr,
+        stderr: String::new(),
+    })
+}
+
+/// Limits of a build: compilers need more memory and write larger files than solutions. The CPU
+/// time is only limited by the timeout.
+fn build_limits(timeout: Duration) -> Limits {
+    Limits {
+        cpu_time: timeout,
+        wall_time: timeout,
+        memory_bytes: 4 * 1024 * 1024 * 1024,
+        output_bytes: 512 * 1024 * 1024,
+        open_files: 1024,
+    }
+}
+
+/// The toolchains of rustup and the crates cargo downloaded, and the `rust-toolchain` files that
+/// select the toolchain of the crate.
+fn toolchain_paths(crate_dir: &Path) -> Vec<PathBuf> {
+    let home_dir = |key: &str, default: &str| {
+        env::var_os(key)
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(default)))
+    };
+
+    let mut paths = vec![];
+    if let Ok(crate_dir) = crate_dir.canonicalize() {
+        for dir in crate_dir.ancestors() {
+            paths.push(dir.join("rust-toolchain"));
+            paths.push(dir.join("rust-toolchain.toml"));
+        }
+    }
+    if let Some(cargo_home) = home_dir("CARGO_HOME", ".cargo") {
+        paths.extend(
+            CARGO_HOME_ENTRIES
+                .iter()
+                .map(|entry| cargo_home.join(entry)),
+        );
+    }
+    paths.extend(home_dir("RUSTUP_HOME", ".rustup"));
+    paths
+}
+
+/// The command with only the `BUILD_ENV` of this process.
+fn command_with_build_env(program: impl AsRef<OsStr>) -> Command {
+    let mut command = Command::new(program);
+    command.env_clear();
+    for key in BUILD_ENV {
+        if let Some(value) = env::var_os(key) {
+            command.env(key, value);
+        }
+    }
+    command
+}
+
+struct Output {
+    success: bool,
+    timed_out: bool,
+    stdout: String,
+    stderr: String,
+}
+
+/// Runs the command in its own process group with the limits, network isolation and seccomp
+/// filter of a run. Besides `access`, the command can only write to a temporary directory, which
+/// is removed afterwards.
+fn run_with_timeout(
+    command: &mut Command,
+    mut access: FileAccess,
+    timeout: Duration,
+) -> io::Result<Output> {
+    let tmp_dir = unique_temp_dir("build");
+    fs::create_dir(&tmp_dir)?;
+    access.write.push(tmp_dir.clone());
+    command.env("TMPDIR", &tmp_dir);
+
+    let output = run_confined(command, &access, timeout);
+    fs::remove_dir_all(&tmp_dir).ok();
+    output
+}
+
+fn run_confined(
+    command: &mut Command,
+    access: &FileAccess,
+    timeout: Duration,
+) -> io::Result<Output> {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    sys::confine(command, &build_limits(timeout), access)?;
+    let mut child = command.spawn()?;
+    let pid = child.id();
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let stdout = thread::spawn(move || read_to_string(stdout));
+    let stderr = thread::spawn(move || read_to_string(stderr));
+
+    let start = Instant::now();
+    let mut timed_out = false;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() > timeout {
+            sys::kill_group(pid);
+            timed_out = true;
+            break child.wait()?;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    // Processes a build script left behind would keep the pipes open.
+    sys::kill_group(pid);
+
+    Ok(Output {
+        success: status.success() && !timed_out,
+        timed_out,
+        stdout: stdout.join().expect("stdout reader panicked")?,
+        stderr: stderr.join().expect("stderr reader panicked")?,
+    })
+}
+
+fn read_to_string(mut reader: impl Read) -> io::Result<String> {
+    let mut text = String::new();
+    reader.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Drops the summaries ("aborting due to ...", "1 warning emitted") that have no location.
+fn to_diagnostic(message: RustcDiagnostic) -> Option<Diagnostic> {
+    if !matches!(message.level.as_str(), "error" | "warning") {
+        return None;
+    }
+    let primary = message.spans.iter().find(|span| span.is_primary);
+    if primary.is_none()
+        && (message.message.starts_with("aborting due to") || message.level == "warning")
+    {
+        return None;
+    }
+
+    let mut notes = vec![];
+    let mut suggestions = vec![];
+    for child in &message.children {
+        let replacements: Vec<&RustcSpan> = child
+            .spans
+            .iter()
+            .filter(|span| span.suggested_replacement.is_some())
+            .collect();
+        if replacements.is_empty() {
+            notes.push(format!("{}: {}", child.level, child.message));
+        }
+        for span in replacements {
+            suggestions.push(Suggestion {
+                message: child.message.clone(),
+                line: span.line_start,
+                column: span.column_start,
+                replacement: span.suggested_replacement.clone().unwrap_or_default(),
+            });
+        }
+    }
+
+    Some(Diagnostic {
+        level: message.level,
+        code: message.code.map(|code| code.code),
+        message: message.message,
+        file: primary.map(|span| span.file_name.clone()),
+        line: primary.map(|span| span.line_start),
+        column: primary.map(|span| span.column_start),
+        label: primary.and_then(|span| span.label.clone()),
+        notes,
+        suggestions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lines of `cargo build --message-format=json`, without the `text` of the spans and the
+    // `explanation` of the codes.
+    const ERROR_WITH_CODE: &str = r#"{"reason":"compiler-message","package_id":"path+file:///tmp/solution#0.1.0","manifest_path":"/tmp/solution/Cargo.toml","target":{"kind":["bin"],"crate_types":["bin"],"name":"solution","src_path":"/tmp/solution/src/main.rs","edition":"2021","doc":true,"doctest":false,"test":true},"message":{"rendered":"error[E0425]: cannot find value `input_strin` in this scope\n --> src/main.rs:5:20\n  |\n5 |     println!(\"{}\", input_strin);\n  |                    ^^^^^^^^^^^\n  |\nhelp: a local variable with a similar name exists\n  |\n5 |     println!(\"{}\", input_string);\n  |                               +\n\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"help","message":"a local variable with a similar name exists","rendered":null,"spans":[{"byte_end":126,"byte_start":115,"column_end":31,"column_start":20,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":5,"line_start":5,"suggested_replacement":"input_string","suggestion_applicability":"MaybeIncorrect"}]}],"level":"error","message":"cannot find value `input_strin` in this scope","spans":[{"byte_end":126,"byte_start":115,"column_end":31,"column_start":20,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":5,"line_start":5,"suggested_replacement":null,"suggestion_applicability":null}],"code":{"code":"E0425","explanation":null}}}"#;
+
+    const ERROR_WITH_LABEL: &str = r#"{"reason":"compiler-message","package_id":"path+file:///tmp/solution#0.1.0","manifest_path":"/tmp/solution/Cargo.toml","target":{"kind":["bin"],"crate_types":["bin"],"name":"solution","src_path":"/tmp/solution/src/main.rs","edition":"2021","doc":true,"doctest":false,"test":true},"message":{"rendered":"error[E0308]: mismatched types\n --> src/main.rs:4:22\n  |\n4 |     let count: i32 = \"3\";\n  |                ---   ^^^ expected `i32`, found `&str`\n  |                |\n  |                expected due to this\n\n","$message_type":"diagnostic","children":[],"level":"error","message":"mismatched types","spans":[{"byte_end":88,"byte_start":85,"column_end":19,"column_start":16,"expansion":null,"file_name":"src/main.rs","is_primary":false,"label":"expected due to this","line_end":4,"line_start":4,"suggested_replacement":null,"suggestion_applicability":null},{"byte_end":94,"byte_start":91,"column_end":25,"column_start":22,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":"expected `i32`, found `&str`","line_end":4,"line_start":4,"suggested_replacement":null,"suggestion_applicability":null}],"code":{"code":"E0308","explanation":null}}}"#;
+
+    const WARNING: &str = r#"{"reason":"compiler-message","package_id":"path+file:///tmp/solution#0.1.0","manifest_path":"/tmp/solution/Cargo.toml","target":{"kind":["bin"],"crate_types":["bin"],"name":"solution","src_path":"/tmp/solution/src/main.rs","edition":"2021","doc":true,"doctest":false,"test":true},"message":{"rendered":"warning: unused variable: `unused`\n --> src/main.rs:2:9\n  |\n2 |     let unused = 1;\n  |         ^^^^^^ help: if this is intentional, prefix it with an underscore: `_unused`\n  |\n  = note: `#[warn(unused_variables)]` on by default\n\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"note","message":"`#[warn(unused_variables)]` on by default","rendered":null,"spans":[]},{"children":[],"code":null,"level":"help","message":"if this is intentional, prefix it with an underscore","rendered":null,"spans":[{"byte_end":26,"byte_start":20,"column_end":15,"column_start":9,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":2,"line_start":2,"suggested_replacement":"_unused","suggestion_applicability":"MachineApplicable"}]}],"level":"warning","message":"unused variable: `unused`","spans":[{"byte_end":26,"byte_start":20,"column_end":15,"column_start":9,"expansion":null,"file_name":"src/main.rs","is_primary":true,"label":null,"line_end":2,"line_start":2,"suggested_replacement":null,"suggestion_applicability":null}],"code":{"code":"unused_variables","explanation":null}}}"#;
+
+    const LINKER_ERROR: &str = r#"{"reason":"compiler-message","package_id":"path+file:///tmp/solution#0.1.0","manifest_path":"/tmp/solution/Cargo.toml","target":{"kind":["bin"],"crate_types":["bin"],"name":"solution","src_path":"/tmp/solution/src/main.rs","edition":"2021","doc":true,"doctest":false,"test":true},"message":{"rendered":"error: linking with `cc` failed: exit status: 1\n  |\n  = note: some arguments are omitted. use `--verbose` to show all linker arguments\n\n","$message_type":"diagnostic","children":[{"children":[],"code":null,"level":"note","message":"some arguments are omitted. use `--verbose` to show all linker arguments","rendered":null,"spans":[]}],"code":null,"level":"error","message":"linking with `cc` failed: exit status: 1","spans":[]}}"#;
+
+    const ABORTING: &str = r#"{"reason":"compiler-message","package_id":"path+file:///tmp/solution#0.1.0","manifest_path":"/tmp/solution/Cargo.toml","target":{"kind":["bin"],"crate_types":["bin"],"name":"solution","src_path":"/tmp/solution/src/main.rs","edition":"2021","doc":true,"doctest":false,"test":true},"message":{"rendered":"error: aborting due to 1 previous error\n\n","$message_type":"diagnostic","children":[],"code":null,"level":"error","message":"aborting due to 1 previous error","spans":[]}}"#;
+
+    const FAILURE_NOTE: &str = r#"{"reason":"compiler-message","package_id":"path+file:///tmp/solution#0.1.0","manifest_path":"/tmp/solution/Cargo.toml","target":{"kind":["bin"],"crate_types":["bin"],"name":"solution","src_path":"/tmp/solution/src/main.rs","edition":"2021","doc":true,"doctest":false,"test":true},"message":{"rendered":"For more information about this error, try `rustc --explain E0308`.\n","$message_type":"diagnostic","children":[],"code":null,"level":"failure-note","message":"For more information about this error, try `rustc --explain E0308`.","spans":[]}}"#;
+
+    fn diagnostic(line: &str) -> Option<Diagnostic> {
+        match serde_json::from_str(line).unwrap() {
+            CargoMessage::CompilerMessage { message } => to_diagnostic(message),
+            _ => panic!("not a compiler message: {line}"),
+        }
+    }
+
+    #[test]
+    fn error_with_code_and_suggestion() {
+        let diagnostic = diagnostic(ERROR_WITH_CODE).unwrap();
+
+        assert_eq!(diagnostic.level, "error");
+        assert_eq!(diagnostic.code.as_deref(), Some("E0425"));
+        assert_eq!(diagnostic.file.as_deref(), Some("src/main.rs"));
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(5), Some(20)));
+        assert!(diagnostic.notes.is_empty());
+        assert_eq!(diagnostic.suggestions.len(), 1);
+        assert_eq!(diagnostic.suggestions[0].replacement, "input_string");
+        assert_eq!(
+            diagnostic.to_string(),
+            "src/main.rs:5:20: error[E0425]: cannot find value `input_strin` in this scope\n  \
+             suggestion at 5:20: a local variable with a similar name exists `input_string`"
+        );
+    }
+
+    #[test]
+    fn label_of_primary_span() {
+        let diagnostic = diagnostic(ERROR_WITH_LABEL).unwrap();
+
+        assert_eq!(
+            diagnostic.label.as_deref(),
+            Some("expected `i32`, found `&str`")
+        );
+        assert_eq!(
+            diagnostic.to_string(),
+            "src/main.rs:4:22: error[E0308]: mismatched types (expected `i32`, found `&str`)"
+        );
+    }
+
+    #[test]
+    fn warning_with_note() {
+        let diagnostic = diagnostic(WARNING).unwrap();
+
+        assert_eq!(diagnostic.level, "warning");
+        assert_eq!(diagnostic.code.as_deref(), Some("unused_variables"));
+        assert_eq!(
+            diagnostic.notes,
+            ["note: `#[warn(unused_variables)]` on by default"]
+        );
+        assert_eq!(
+            diagnostic.to_string(),
+            "src/main.rs:2:9: warning[unused_variables]: unused variable: `unused`\n  \
+             note: `#[warn(unused_variables)]` on by default\n  \
+             suggestion at 2:9: if this is intentional, prefix it with an underscore `_unused`"
+        );
+    }
+
+    #[test]
+    fn error_without_span() {
+        let diagnostic = diagnostic(LINKER_ERROR).unwrap();
+
+        assert_eq!(diagnostic.file, None);
+        assert_eq!(
+            diagnostic.to_string(),
+            "error: linking with `cc` failed: exit status: 1\n  \
+             note: some arguments are omitted. use `--verbose` to show all linker arguments"
+        );
+    }
+
+    #[test]
+    fn drops_summaries() {
+        assert!(diagnostic(ABORTING).is_none());
+        assert!(diagnostic(FAILURE_NOTE).is_none());
+    }
+
+    #[test]
+    fn other_messages() {
+        let artifact = r#"{"reason":"compiler-artifact","package_id":"path+file:///tmp/solution#0.1.0","executable":"/tmp/solution/target/release/solution","fresh":false}"#;
+        assert!(matches!(
+            serde_json::from_str(artifact).unwrap(),
+            CargoMessage::CompilerArtifact {
+                executable: Some(_)
+            }
+        ));
+        assert!(matches!(
+            serde_json::from_str(r#"{"reason":"build-finished","success":true}"#).unwrap(),
+            CargoMessage::Other
+        ));
+    }
+}
@@ -0,0 +1,369 @@
+//! Builds solver crates and runs the untrusted binaries with resource limits.
+//!
+//! `build` compiles a crate and reports the compiler errors and warnings as structured
+//! diagnostics, compact enough to be fed back to an LLM. Other languages are supported through
+//! the `Registry` of runtimes.
+//!
+//! The binary is started in a fresh working directory that only contains a read-only
+//! `input.txt` (and, with `run`, a copy of the binary), with an empty environment and the
+//! following limits:
+//! - CPU time, memory (address space), size of written files, number of open files
+//!   (rlimits), no core dumps
+//! - wall time: the process group is killed once it runs out
+//! - output: stdout and stderr are cut off after `Limits::output_bytes`
+//! - file system (Linux with Landlock, otherwise runs fail): the program can read its working
+//!   directory, itself and the system programs and libraries (`/usr`, `/lib`, ...), nothing else;
+//!   it cannot write anywhere. It cannot signal processes outside of the sandbox either.
+//! - user: started by root, the program runs as `nobody`
+//! - network: on Linux the process gets its own (empty) network namespace where unprivileged
+//!   user namespaces are allowed; in addition, a seccomp filter rejects creating sockets
+//!
+//! Builds run with the same network isolation, seccomp filter and Landlock rules, with only the
+//! environment the toolchains need and with limits for compilers. They can additionally read the
+//! toolchains and write to the crate, the target directory and a temporary directory.
+
+mod build;
+mod runtime;
+mod sys;
+
+use std::{
+    env, fs,
+    io::{self, Read},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use sys::FileAccess;
+
+pub use build::{build, build_command, build_with_target_dir, Build, Diagnostic, Suggestion};
+pub use runtime::{BuildStep, Registry, Runtime, Solve};
+
+pub const INPUT_FILE: &str = "input.txt";
+/// Name of the copy of the binary `run` starts.
+const PROGRAM_FILE: &str = "program";
+
+/// Durations are (de)serialized as seconds, missing fields get the default value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    #[serde(with = "secs")]
+    pub cpu_time: Duration,
+    #[serde(with = "secs")]
+    pub wall_time: Duration,
+    pub memory_bytes: u64,
+    pub output_bytes: usize,
+    pub open_files: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            cpu_time: Duration::from_secs(10),
+            wall_time: Duration::from_secs(20),
+            memory_bytes: 1024 * 1024 * 1024,
+            output_bytes: 32768,
+            open_files: 64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Execution {
+    /// Exit code if the program exited normally.
+    pub exit_code: Option<i32>,
+    /// Signal that terminated the program, e.g. `9` (SIGKILL) after the wall time ran out.
+    pub signal: Option<i32>,
+    /// Whether the program ran out of CPU or wall time.
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+    /// Whether stdout or stderr were cut off.
+    pub output_truncated: bool,
+    pub wall_time_ms: u64,
+    pub cpu_time_ms: u64,
+}
+
+impl Execution {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+/// Fails if runs and builds cannot be confined on this system (see the module documentation), so
+/// callers can refuse untrusted code up front instead of on every run.
+pub fn check_confinement() -> io::Result<()> {
+    sys::check_confinement()
+}
+
+/// Runs `binary` with `input` as `input.txt` in its working directory. The binary is copied there
+/// first, so it does not need to be accessible to the sandbox where it was built.
+pub fn run(binary: &Path, input: &[u8], limits: &Limits) -> io::Result<Execution> {
+    let dir = WorkDir::create(input, Some(binary))?;
+    let program = dir.0.join(PROGRAM_FILE);
+    execute(&dir, &program, &[], vec![], limits)
+}
+
+/// Like `run`, with arguments, e.g. an interpreter and the script to run. Besides its working
+/// directory and the system directories, the program can only read the `readable` files and
+/// directories (and itself), e.g. the directory of the script.
+pub fn run_command(
+    program: &Path,
+    args: &[String],
+    readable: &[PathBuf],
+    input: &[u8],
+    limits: &Limits,
+) -> io::Result<Execution> {
+    let program = program.canonicalize()?;
+    let dir = WorkDir::create(input, None)?;
+    let mut readable = readable.to_vec();
+    readable.push(program.clone());
+    execute(&dir, &program, args, readable, limits)
+}
+
+fn execute(
+    dir: &WorkDir,
+    program: &Path,
+    args: &[String],
+    mut readable: Vec<PathBuf>,
+    limits: &Limits,
+) -> io::Result<Execution> {
+    readable.push(dir.0.clone());
+    let access = FileAccess {
+        read: readable,
+        write: vec![],
+    };
+
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .current_dir(&dir.0)
+        .env_clear()
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    sys::drop_privileges(&mut command);
+    sys::confine(&mut command, limits, &access)?;
+
+    let start = Instant::now();
+    let mut child = command.spawn()?;
+    let pid = child.id();
+
+    let output_bytes = limits.output_bytes;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+    let stdout = thread::spawn(move || read_capped(stdout, output_bytes));
+    let stderr = thread::spawn(move || read_capped(stderr, output_bytes));
+
+    let mut wall_timed_out = false;
+    let status = loop {
+        if let Some(status) = sys::try_wait(pid)? {
+            break status;
+        }
+        if start.elapsed() > limits.wall_time {
+            sys::kill_group(pid);
+            wall_timed_out = true;
+            break sys::wait(pid)?;
+        }
+        thread::sleep(Duration::from_millis(1));
+    };
+    let wall_time = start.elapsed();
+
+    // Children the program left behind would keep the pipes open.
+    sys::kill_group(pid);
+    let (stdout, stdout_truncated) = stdout.join().expect("stdout reader panicked")?;
+    let (stderr, stderr_truncated) = stderr.join().expect("stderr reader panicked")?;
+
+    Ok(Execution {
+        exit_code: status.exit_code,
+        signal: status.signal,
+        timed_out: wall_timed_out || status.signal == Some(libc::SIGXCPU),
+        stdout,
+        stderr,
+        output_truncated: stdout_truncated || stderr_truncated,
+        wall_time_ms: wall_time.as_millis() as u64,
+        cpu_time_ms: status.cpu_time.as_millis() as u64,
+    })
+}
+
+/// Reads everything, but keeps only the first `limit` bytes, so a chatty program can neither
+/// block on a full pipe nor fill up the memory of the caller.
+fn read_capped(mut reader: impl Read, limit: usize) -> io::Result<(String, bool)> {
+    let mut buffer = vec![];
+    (&mut reader).take(limit as u64).read_to_end(&mut buffer)?;
+    let discarded = io::copy(&mut reader, &mut io::sink())?;
+    Ok((String::from_utf8_lossy(&buffer).into_owned(), discarded > 0))
+}
+
+mod secs {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(duration.as_secs_f64())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let secs = f64::deserialize(deserializer)?;
+        Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom)
+    }
+}
+
+/// A path in the temp directory that no other run (of this or another process) uses.
+fn unique_temp_dir(prefix: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    env::temp_dir().join(format!(
+        "{prefix}-{}-{}-{}",
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// A temporary working directory containing only the read-only input file and, if given, a copy
+/// of the program. Removed on drop.
+struct WorkDir(PathBuf);
+
+impl WorkDir {
+    fn create(input: &[u8], program: Option<&Path>) -> io::Result<Self> {
+        let dir = unique_temp_dir("sandbox");
+        fs::create_dir(&dir)?;
+        let work_dir = Self(dir);
+        let input_file = work_dir.0.join(INPUT_FILE);
+        fs::write(&input_file, input)?;
+        fs::set_permissions(&input_file, fs::Permissions::from_mode(0o444))?;
+        if let Some(program) = program {
+            let program_file = work_dir.0.join(PROGRAM_FILE);
+            fs::copy(program, &program_file)?;
+            fs::set_permissions(&program_file, fs::Permissions::from_mode(0o555))?;
+        }
+        fs::set_permissions(&work_dir.0, fs::Permissions::from_mode(0o555))?;
+        Ok(work_dir)
+    }
+}
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        fs::set_permissions(&self.0, fs::Permissions::from_mode(0o755)).ok();
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_up_to_the_limit_is_kept() {
+        assert_eq!(
+            read_capped(&b"hello"[..], 5).unwrap(),
+            ("hello".to_string(), false)
+        );
+        assert_eq!(read_capped(&b""[..], 5).unwrap(), (String::new(), false));
+    }
+
+    #[test]
+    fn output_over_the_limit_is_cut_off() {
+        let output = vec![b'x'; 100_000];
+
+        let (text, truncated) = read_capped(output.as_slice(), 6).unwrap();
+
+        assert_eq!(text, "xxxxxx");
+        assert!(truncated);
+    }
+
+    #[test]
+    fn invalid_utf8_is_replaced() {
+        let (text, truncated) = read_capped(&b"ok \xff\xfe done"[..], 100).unwrap();
+        assert_eq!(text, "ok \u{fffd}\u{fffd} done");
+        assert!(!truncated);
+
+        // The limit can split a character.
+        let (text, truncated) = read_capped("ä".as_bytes(), 1).unwrap();
+        assert_eq!(text, "\u{fffd}");
+        assert!(truncated);
+    }
+
+    #[test]
+    fn durations_are_seconds() {
+        let limits = Limits {
+            cpu_time: Duration::from_millis(1500),
+            wall_time: Duration::from_secs(3),
+            ..Limits::default()
+        };
+
+        let json = serde_json::to_value(limits).unwrap();
+        assert_eq!(json["cpu_time"], 1.5);
+        assert_eq!(json["wall_time"], 3.0);
+
+        let limits: Limits = serde_json::from_value(json).unwrap();
+        assert_eq!(limits.cpu_time, Duration::from_millis(1500));
+        assert_eq!(limits.wall_time, Duration::from_secs(3));
+    }
+
+    #[test]
+    fn missing_limits_get_the_default_and_negative_durations_are_rejected() {
+        let limits: Limits = serde_json::from_str(r#"{"wall_time": 0.25}"#).unwrap();
+        assert_eq!(limits.wall_time, Duration::from_millis(250));
+        assert_eq!(limits.cpu_time, Limits::default().cpu_time);
+
+        assert!(serde_json::from_str::<Limits>(r#"{"cpu_time": -1}"#).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[ignore = "needs Landlock and python3 in /usr/bin"]
+    fn runs_are_confined() {
+        let limits = Limits {
+            wall_time: Duration::from_millis(300),
+            ..Limits::default()
+        };
+
+        let sleep =
+            run_command(Path::new("/bin/sleep"), &["10".into()], &[], b"", &limits).unwrap();
+        assert!(sleep.timed_out);
+        assert_eq!(sleep.signal, Some(libc::SIGKILL));
+        assert!(sleep.wall_time_ms < 5000);
+
+        let socket = run_command(
+            Path::new("/usr/bin/python3"),
+            &["-c".into(), "import socket; socket.socket()".into()],
+            &[],
+            b"",
+            &Limits::default(),
+        )
+        .unwrap();
+        assert!(!socket.success());
+        assert!(
+            socket.stderr.contains("PermissionError"),
+            "{}",
+            socket.stderr
+        );
+
+        let secret = unique_temp_dir("secret");
+        fs::write(&secret, "token").unwrap();
+        let cat = |file: &Path| {
+            let args = [file.to_string_lossy().into_owned()];
+            run_command(Path::new("/bin/cat"), &args, &[], b"input", &limits).unwrap()
+        };
+        let denied = cat(&secret);
+        let input = cat(Path::new(INPUT_FILE));
+        fs::remove_file(&secret).unwrap();
+        assert!(!denied.success());
+        assert!(
+            denied.stderr.contains("Permission denied"),
+            "{}",
+            denied.stderr
+        );
+        assert_eq!(input.stdout, "input");
+    }
+}
@@ -0,0 +1,131 @@
+use std::{env, error::Error, fs, path::Path, time::Duration};
+
+use sandbox::{build, run, Limits, Registry};
+
+const USAGE: &str = "usage: sandbox-rs [--cpu-secs <n>] [--wall-secs <n>] [--memory-mb <n>] \
+[--output-kb <n>] [--open-files <n>] <binary> <input file>
+       sandbox-rs build [--compact] [--timeout-secs <n>] <crate dir>
+       sandbox-rs runtimes
+       sandbox-rs template <runtime>
+       sandbox-rs solve <runtime> <source file> <input file>";
+
+const DEFAULT_BUILD_TIMEOUT: Duration = Duration::from_secs(120);
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.split_first() {
+        Some((command, args)) if command == "build" => build_crate(args),
+        Some((command, [])) if command == "runtimes" => list_runtimes(),
+        Some((command, [runtime])) if command == "template" => print_template(runtime),
+        Some((command, [runtime, source_file, input_file])) if command == "solve" => {
+            solve(runtime, source_file, input_file)
+        }
+        _ => run_binary(&args),
+    }
+}
+
+/// The built-in runtimes, or the ones of the JSON file in `RUNTIMES_FILE`, if installed.
+fn registry() -> Result<Registry, Box<dyn Error>> {
+    let registry = match env::var_os("RUNTIMES_FILE") {
+        Some(path) => Registry::from_file(Path::new(&path))?,
+        None => Registry::builtin(&Limits::default()),
+    };
+    Ok(registry.available())
+}
+
+fn list_runtimes() -> Result<(), Box<dyn Error>> {
+    for runtime in registry()?.runtimes {
+        println!("{}", runtime.name);
+    }
+    Ok(())
+}
+
+fn print_template(name: &str) -> Result<(), Box<dyn Error>> {
+    let registry = registry()?;
+    let runtime = registry
+        .get(name)
+        .ok_or_else(|| format!("unknown runtime {name}"))?;
+    print!("{}", runtime.template);
+    Ok(())
+}
+
+/// Builds and runs a solution with the limits of its runtime and prints the result as JSON.
+fn solve(name: &str, source_file: &str, input_file: &str) -> Result<(), Box<dyn Error>> {
+    let registry = registry()?;
+    let runtime = registry
+        .get(name)
+        .ok_or_else(|| format!("unknown runtime {name}"))?;
+    let source = fs::read_to_string(source_file)?;
+    let input = fs::read(input_file)?;
+    let solve = runtime.solve(&source, &input, DEFAULT_BUILD_TIMEOUT)?;
+
+    println!("{}", serde_json::to_string_pretty(&solve)?);
+    Ok(())
+}
+
+/// Prints the build result as JSON, or with `--compact` only the diagnostics, one per line.
+fn build_crate(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut compact = false;
+    let mut timeout = DEFAULT_BUILD_TIMEOUT;
+    let mut crate_dir = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--compact" => compact = true,
+            "--timeout-secs" => {
+                timeout = Duration::from_secs(args.next().ok_or(USAGE)?.parse()?);
+            }
+            _ if crate_dir.is_none() && !arg.starts_with("--") => crate_dir = Some(arg),
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    let build = build(Path::new(crate_dir.ok_or(USAGE)?), timeout)?;
+    if !compact {
+        println!("{}", serde_json::to_string_pretty(&build)?);
+        return Ok(());
+    }
+
+    for diagnostic in &build.diagnostics {
+        println!("{diagnostic}");
+    }
+    if build.timed_out {
+        println!("error: build timed out");
+    } else if !build.success && build.diagnostics.is_empty() {
+        print!("{}", build.stderr);
+    }
+    Ok(())
+}
+
+fn run_binary(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut limits = Limits::default();
+    let mut paths = vec![];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            paths.push(arg);
+            continue;
+        }
+
+        let value: u64 = args.next().ok_or(USAGE)?.parse()?;
+        match arg.as_str() {
+            "--cpu-secs" => limits.cpu_time = Duration::from_secs(value),
+            "--wall-secs" => limits.wall_time = Duration::from_secs(value),
+            "--memory-mb" => limits.memory_bytes = value * 1024 * 1024,
+            "--output-kb" => limits.output_bytes = value as usize * 1024,
+            "--open-files" => limits.open_files = value,
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    let [binary, input_file] = paths.as_slice() else {
+        return Err(USAGE.into());
+    };
+    let input = fs::read(input_file)?;
+    let execution = run(Path::new(binary), &input, &limits)?;
+
+    println!("{}", serde_json::to_string_pretty(&execution)?);
+    Ok(())
+}
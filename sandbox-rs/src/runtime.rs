@@ -0,0 +1,258 @@
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{build, build_command, run_command, unique_temp_dir, Build, Execution, Limits};
+
+/// Replaced by the directory the source was written to in build and run commands.
+const DIR_PLACEHOLDER: &str = "{dir}";
+
+const RUST_CARGO_TOML: &str = r#"[package]
+name = "solution"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+"#;
+
+/// How solutions of one language are built and run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Runtime {
+    pub name: String,
+    /// Path of the source file, relative to the directory of the solution.
+    pub source_file: String,
+    /// Starting point handed to the LLM: reads `input.txt` and prints the result.
+    pub template: String,
+    /// Other files the build needs, e.g. `Cargo.toml`.
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+    pub build: Option<BuildStep>,
+    /// Program and arguments. Programs without a path are looked up in `PATH`.
+    pub run: Vec<String>,
+    #[serde(default)]
+    pub limits: Limits,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuildStep {
+    /// `cargo build`, with structured diagnostics.
+    Cargo,
+    /// Program and arguments, e.g. a compiler or a syntax check.
+    Command(Vec<String>),
+}
+
+/// The result of building and running a solution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Solve {
+    pub runtime: String,
+    pub build: Build,
+    /// `None` if the build failed.
+    pub execution: Option<Execution>,
+}
+
+impl Runtime {
+    /// Whether the programs needed to build and run solutions are installed.
+    pub fn is_available(&self) -> bool {
+        let build_program = match &self.build {
+            Some(BuildStep::Cargo) => Some("cargo"),
+            Some(BuildStep::Command(command)) => command.first().map(String::as_str),
+            None => None,
+        };
+        let run_program = self.run.first().map(String::as_str);
+
+        [build_program, run_program]
+            .into_iter()
+            .flatten()
+            .filter(|program| !program.starts_with(DIR_PLACEHOLDER))
+            .all(|program| find_program(program).is_some())
+    }
+
+    /// Writes the source to a fresh directory, builds it and runs it against `input`. The directory
+    /// is removed afterwards.
+    pub fn solve(&self, source: &str, input: &[u8], build_timeout: Duration) -> io::Result<Solve> {
+        let dir = unique_temp_dir("solution");
+        let solve = self.solve_in(&dir, source, input, build_timeout);
+        fs::remove_dir_all(&dir).ok();
+        solve
+    }
+
+    fn solve_in(
+        &self,
+        dir: &Path,
+        source: &str,
+        input: &[u8],
+        build_timeout: Duration,
+    ) -> io::Result<Solve> {
+        fs::create_dir_all(dir)?;
+        let dir = dir.canonicalize()?;
+        for (file, content) in &self.files {
+            write_file(&dir.join(file), content)?;
+        }
+        write_file(&dir.join(&self.source_file), source)?;
+
+        let build = match &self.build {
+            Some(BuildStep::Cargo) => build(&dir, build_timeout)?,
+            Some(BuildStep::Command(command)) => {
+                let (program, args) = self.resolve(command, &dir)?;
+                build_command(&dir, &program, &args, build_timeout)?
+            }
+            None => Build {
+                success: true,
+                timed_out: false,
+                executable: None,
+                diagnostics: vec![],
+                rendered: String::new(),
+                stderr: String::new(),
+            },
+        };
+        if !build.success {
+            return Ok(Solve {
+                runtime: self.name.clone(),
+                build,
+                execution: None,
+            });
+        }
+
+        let (program, args) = self.resolve(&self.run, &dir)?;
+        let execution = run_command(&program, &args, &[dir.clone()], input, &self.limits)?;
+
+        Ok(Solve {
+            runtime: self.name.clone(),
+            build,
+            execution: Some(execution),
+        })
+    }
+
+    fn resolve(&self, command: &[String], dir: &Path) -> io::Result<(PathBuf, Vec<String>)> {
+        let dir = dir.to_string_lossy();
+        let mut command = command.iter().map(|arg| arg.replace(DIR_PLACEHOLDER, &dir));
+
+        let program = command.next().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("runtime {} has an empty command", self.name),
+            )
+        })?;
+        let program = find_program(&program).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{program} not found (runtime {})", self.name),
+            )
+        })?;
+
+        Ok((program, command.collect()))
+    }
+}
+
+/// The runtimes solutions can be written for, selected by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Registry {
+    pub runtimes: Vec<Runtime>,
+}
+
+impl Registry {
+    /// Rust (with the `solver-rs` template), Python, JavaScript (Node.js) and C++, all with the
+    /// same limits.
+    pub fn builtin(limits: &Limits) -> Self {
+        let runtime =
+            |name: &str, source_file: &str, template: &str, build, run: &[&str]| Runtime {
+                name: name.into(),
+                source_file: source_file.into(),
+                template: template.into(),
+                files: BTreeMap::new(),
+                build,
+                run: run.iter().map(|arg| arg.to_string()).collect(),
+                limits: limits.clone(),
+            };
+        let command = |args: &[&str]| {
+            Some(BuildStep::Command(
+                args.iter().map(|arg| arg.to_string()).collect(),
+            ))
+        };
+
+        let mut rust = runtime(
+            "rust",
+            "src/main.rs",
+            include_str!("../../solver-rs/src/main_template.rs"),
+            Some(BuildStep::Cargo),
+            &["{dir}/target/release/solution"],
+        );
+        rust.files
+            .insert("Cargo.toml".into(), RUST_CARGO_TOML.into());
+
+        Self {
+            runtimes: vec![
+                rust,
+                runtime(
+                    "python",
+                    "main.py",
+                    include_str!("../templates/main_template.py"),
+                    command(&["python3", "-m", "py_compile", "{dir}/main.py"]),
+                    &["python3", "{dir}/main.py"],
+                ),
+                runtime(
+                    "javascript",
+                    "main.js",
+                    include_str!("../templates/main_template.js"),
+                    command(&["node", "--check", "{dir}/main.js"]),
+                    &["node", "{dir}/main.js"],
+                ),
+                runtime(
+                    "cpp",
+                    "main.cpp",
+                    include_str!("../templates/main_template.cpp"),
+                    command(&[
+                        "g++",
+                        "-O2",
+                        "-std=c++17",
+                        "-o",
+                        "{dir}/solution",
+                        "{dir}/main.cpp",
+                    ]),
+                    &["{dir}/solution"],
+                ),
+            ],
+        }
+    }
+
+    /// Reads a registry from a JSON file in the format `Registry` is serialized to.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Keeps only the runtimes whose toolchains are installed.
+    pub fn available(mut self) -> Self {
+        self.runtimes.retain(Runtime::is_available);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Runtime> {
+        self.runtimes.iter().find(|runtime| runtime.name == name)
+    }
+}
+
+/// Resolves a program without a path via `PATH`, since solutions run with an empty environment.
+fn find_program(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return path.exists().then_some(path);
+    }
+
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(program))
+        .find(|path| path.is_file())
+}
+
+fn write_file(path: &Path, content: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, content)
+}
@@ -0,0 +1,423 @@
+use std::{io, os::unix::process::CommandExt, path::PathBuf, process::Command, time::Duration};
+
+use crate::Limits;
+
+/// User and group ID of `nobody`, which programs run as when started by root.
+const NOBODY: u32 = 65534;
+
+/// What every program may read: the system programs and libraries, and a few devices.
+const SYSTEM_PATHS: &[&str] = &[
+    "/usr",
+    "/bin",
+    "/sbin",
+    "/lib",
+    "/lib32",
+    "/lib64",
+    "/etc/ld.so.cache",
+    // Node.js fails to start without it.
+    "/etc/ssl/openssl.cnf",
+    "/dev/zero",
+    "/dev/random",
+    "/dev/urandom",
+];
+
+/// What every program may write to.
+const SYSTEM_WRITABLE_PATHS: &[&str] = &["/dev/null"];
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+type Resource = libc::c_int;
+
+pub struct Status {
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub cpu_time: Duration,
+}
+
+/// The parts of the file system a confined process can access, besides `SYSTEM_PATHS`. Everything
+/// else, like the home directory or the database of the caller, cannot be opened.
+#[derive(Debug, Default)]
+pub struct FileAccess {
+    /// Files and directories that can be read and executed.
+    pub read: Vec<PathBuf>,
+    /// Files and directories that can also be written.
+    pub write: Vec<PathBuf>,
+}
+
+/// Sets up the limits in the child process, between `fork` and `exec`. Fails if the file system
+/// access cannot be restricted (Linux without Landlock, other systems).
+pub fn confine(command: &mut Command, limits: &Limits, access: &FileAccess) -> io::Result<()> {
+    let ruleset = landlock::ruleset(access)?;
+    // Whole seconds, rounded up: a limit of 0 would mean no CPU time at all.
+    let cpu_secs = (limits.cpu_time.as_millis() as u64).div_ceil(1000).max(1);
+    let memory_bytes = limits.memory_bytes;
+    let file_bytes = limits.output_bytes as u64;
+    let open_files = limits.open_files;
+
+    // SAFETY: only async-signal-safe system calls are made in the child.
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setpgid(0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // The soft limit sends SIGXCPU, the hard limit one second later SIGKILL.
+            set_rlimit(libc::RLIMIT_CPU, cpu_secs, cpu_secs + 1)?;
+            set_rlimit(libc::RLIMIT_AS, memory_bytes, memory_bytes)?;
+            set_rlimit(libc::RLIMIT_FSIZE, file_bytes, file_bytes)?;
+            set_rlimit(libc::RLIMIT_NOFILE, open_files, open_files)?;
+            set_rlimit(libc::RLIMIT_CORE, 0, 0)?;
+            isolate_network()?;
+            landlock::restrict_self(&ruleset)
+        });
+    }
+    Ok(())
+}
+
+/// Fails like `confine` would if the file system access cannot be restricted.
+pub fn check_confinement() -> io::Result<()> {
+    landlock::ruleset(&FileAccess::default()).map(drop)
+}
+
+/// Lets the program run as `nobody` if this process runs as root, so it cannot touch the files
+/// of the caller even where the file system access is not restricted otherwise.
+pub fn drop_privileges(command: &mut Command) {
+    if unsafe { libc::geteuid() } == 0 {
+        command.uid(NOBODY).gid(NOBODY);
+    }
+}
+
+fn set_rlimit(resource: Resource, soft: u64, hard: u64) -> io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: soft as libc::rlim_t,
+        rlim_max: hard as libc::rlim_t,
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn isolate_network() -> io::Result<()> {
+    // Fails where unprivileged user namespaces are disabled; the seccomp filter still applies.
+    unsafe { libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) };
+    deny_sockets()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn isolate_network() -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+/// Installs a seccomp filter that lets `socket` fail with `EACCES`. System calls of other
+/// architectures (and the x32 ABI) are rejected as well, so the filter cannot be bypassed.
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+fn deny_sockets() -> io::Result<()> {
+    const ARCH_OFFSET: u32 = 4;
+    const NR_OFFSET: u32 = 0;
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    let statement = |code: u32, k: u32| libc::sock_filter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    };
+    let jump = |code: u32, k: u32, jt: u8, jf: u8| libc::sock_filter {
+        code: code as u16,
+        jt,
+        jf,
+        k,
+    };
+    let filter = [
+        statement(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, ARCH_OFFSET),
+        jump(
+            libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+            AUDIT_ARCH,
+            1,
+            0,
+        ),
+        statement(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+        statement(libc::BPF_LD | libc::BPF_W | libc::BPF_ABS, NR_OFFSET),
+        jump(
+            libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K,
+            X32_SYSCALL_BIT,
+            2,
+            0,
+        ),
+        jump(
+            libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K,
+            libc::SYS_socket as u32,
+            1,
+            0,
+        ),
+        statement(libc::BPF_RET | libc::BPF_K, libc::SECCOMP_RET_ALLOW),
+        statement(
+            libc::BPF_RET | libc::BPF_K,
+            libc::SECCOMP_RET_ERRNO | libc::EACCES as u32,
+        ),
+    ];
+    let program = libc::sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_ptr() as *mut libc::sock_filter,
+    };
+
+    unsafe {
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if libc::prctl(
+            libc::PR_SET_SECCOMP,
+            libc::SECCOMP_MODE_FILTER,
+            &program as *const libc::sock_fprog,
+        ) != 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(
+    target_os = "linux",
+    not(any(target_arch = "x86_64", target_arch = "aarch64"))
+))]
+fn deny_sockets() -> io::Result<()> {
+    Ok(())
+}
+
+/// Restricts the file system access with Landlock: the ruleset is created by the caller, the child
+/// only enforces it on itself.
+#[cfg(target_os = "linux")]
+mod landlock {
+    use std::{
+        fs::{File, OpenOptions},
+        io,
+        os::{
+            fd::{AsRawFd, FromRawFd, OwnedFd},
+            unix::fs::OpenOptionsExt,
+        },
+        path::Path,
+    };
+
+    use super::{FileAccess, SYSTEM_PATHS, SYSTEM_WRITABLE_PATHS};
+
+    const CREATE_RULESET_VERSION: u32 = 1 << 0;
+    const RULE_PATH_BENEATH: libc::c_int = 1;
+
+    const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+    const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+    const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+    /// Removing and creating files, directories, links etc. (ABI 1).
+    const ACCESS_FS_ABI_1: u64 = (1 << 13) - 1;
+    const ACCESS_FS_REFER: u64 = 1 << 13;
+    const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+    const ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+    /// The rights that apply to files (and not only to directories).
+    const ACCESS_FILE: u64 = ACCESS_FS_EXECUTE
+        | ACCESS_FS_WRITE_FILE
+        | ACCESS_FS_READ_FILE
+        | ACCESS_FS_TRUNCATE
+        | ACCESS_FS_IOCTL_DEV;
+    const ACCESS_READ: u64 = ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+
+    const SCOPE_ABSTRACT_UNIX_SOCKET: u64 = 1 << 0;
+    const SCOPE_SIGNAL: u64 = 1 << 1;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+        handled_access_net: u64,
+        scoped: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: libc::c_int,
+    }
+
+    /// A ruleset that denies everything the kernel knows of, except for `access`. Signals to and
+    /// abstract sockets of processes outside of the sandbox are denied as well (ABI 6).
+    pub fn ruleset(access: &FileAccess) -> io::Result<OwnedFd> {
+        let abi = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0,
+                CREATE_RULESET_VERSION,
+            )
+        };
+        if abi < 0 {
+            let error = io::Error::last_os_error();
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("cannot restrict file system access, Landlock is not available: {error}"),
+            ));
+        }
+
+        let mut handled = ACCESS_FS_ABI_1;
+        if abi >= 2 {
+            handled |= ACCESS_FS_REFER;
+        }
+        if abi >= 3 {
+            handled |= ACCESS_FS_TRUNCATE;
+        }
+        if abi >= 5 {
+            handled |= ACCESS_FS_IOCTL_DEV;
+        }
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+            handled_access_net: 0,
+            scoped: if abi >= 6 {
+                SCOPE_ABSTRACT_UNIX_SOCKET | SCOPE_SIGNAL
+            } else {
+                0
+            },
+        };
+        // Older kernels reject the fields they don't know.
+        let size = match abi {
+            1..=3 => 8,
+            4 | 5 => 16,
+            _ => std::mem::size_of::<RulesetAttr>(),
+        };
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const RulesetAttr,
+                size,
+                0,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let ruleset = unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) };
+
+        let system_paths = SYSTEM_PATHS.iter().map(Path::new);
+        for path in system_paths.chain(access.read.iter().map(|path| path.as_path())) {
+            add_rule(&ruleset, path, ACCESS_READ)?;
+        }
+        let system_writable_paths = SYSTEM_WRITABLE_PATHS.iter().map(Path::new);
+        for path in system_writable_paths.chain(access.write.iter().map(|path| path.as_path())) {
+            add_rule(&ruleset, path, handled)?;
+        }
+        Ok(ruleset)
+    }
+
+    /// Paths that don't exist (like `/lib32` on most systems) are skipped.
+    fn add_rule(ruleset: &OwnedFd, path: &Path, access: u64) -> io::Result<()> {
+        let file = match OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_PATH | libc::O_CLOEXEC)
+            .open(path)
+        {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(error),
+        };
+        let access = if File::metadata(&file)?.is_dir() {
+            access
+        } else {
+            access & ACCESS_FILE
+        };
+
+        let attr = PathBeneathAttr {
+            allowed_access: access,
+            parent_fd: file.as_raw_fd(),
+        };
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                RULE_PATH_BENEATH,
+                &attr as *const PathBeneathAttr,
+                0,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Called in the child, between `fork` and `exec`.
+    pub fn restrict_self(ruleset: &OwnedFd) -> io::Result<()> {
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod landlock {
+    use std::io;
+
+    use super::FileAccess;
+
+    pub struct Ruleset;
+
+    pub fn ruleset(_access: &FileAccess) -> io::Result<Ruleset> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "cannot restrict file system access, Landlock needs Linux",
+        ))
+    }
+
+    pub fn restrict_self(_ruleset: &Ruleset) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns the status if the process has exited, without blocking.
+pub fn try_wait(pid: u32) -> io::Result<Option<Status>> {
+    wait4(pid, libc::WNOHANG)
+}
+
+pub fn wait(pid: u32) -> io::Result<Status> {
+    Ok(wait4(pid, 0)?.expect("blocking wait4 returns a status"))
+}
+
+fn wait4(pid: u32, options: libc::c_int) -> io::Result<Option<Status>> {
+    let mut status = 0;
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::wait4(pid as libc::pid_t, &mut status, options, &mut usage) };
+    if result < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if result == 0 {
+        return Ok(None);
+    }
+
+    let cpu_time = timeval_to_duration(usage.ru_utime) + timeval_to_duration(usage.ru_stime);
+    Ok(Some(Status {
+        exit_code: libc::WIFEXITED(status).then(|| libc::WEXITSTATUS(status)),
+        signal: libc::WIFSIGNALED(status).then(|| libc::WTERMSIG(status)),
+        cpu_time,
+    }))
+}
+
+fn timeval_to_duration(time: libc::timeval) -> Duration {
+    Duration::from_secs(time.tv_sec as u64) + Duration::from_micros(time.tv_usec as u64)
+}
+
+/// Kills the process and everything it started (the child is the leader of its own group).
+pub fn kill_group(pid: u32) {
+    unsafe { libc::kill(-(pid as libc::pid_t), libc::SIGKILL) };
+}
@@ -0,0 +1,17 @@
+#include <fstream>
+#include <iostream>
+#include <sstream>
+#include <string>
+
+int main() {
+    std::ifstream file("input.txt");
+    std::stringstream buffer;
+    buffer << file.rdbuf();
+    std::string input_string = buffer.str();
+    std::string result = "";
+
+    // your code here
+
+    std::cout << result << std::endl;
+    return 0;
+}
@@ -0,0 +1,12 @@
+const fs = require("fs");
+
+function main() {
+    const inputString = fs.readFileSync("input.txt", "utf8");
+    let result = "";
+
+    // your code here
+
+    console.log(result);
+}
+
+main();
@@ -0,0 +1,12 @@
+def main():
+    with open("input.txt") as f:
+        input_string = f.read()
+    result = ""
+
+    # your code here
+
+    print(result)
+
+
+if __name__ == "__main__":
+    main()
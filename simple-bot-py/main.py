@@ -135,8 +135,6 @@ The challenge is:
     
 def solve_advent_of_code(description, input_text, prompt):
     template_file = '../solver-rs/src/main_template.rs'
-    main_file = '../solver-rs/src/main.rs'
-    input_file = '../solver-rs/input.txt'
     
     with open(template_file, 'r') as f:
         template = f.read()
@@ -153,19 +151,24 @@ def solve_advent_of_code(description, input_text, prompt):
     end = response.find('```', start + 1)
     rust_code = response[start + 7:end]
     
-    with open(main_file, 'w') as f:
-        f.write(rust_code)
+    # every attempt gets its own crate in solver-rs, so attempts don't overwrite each other:
+    attempt_id = run_solver(['new']).strip()
+    try:
+        run_solver(['source', attempt_id], rust_code)
+        run_solver(['input', attempt_id], input_text)
         
-    with open(input_file, 'w') as f:
-        f.write(input_text)
-    
-    print('Trying to run the generated code...')
-    # run cargo run in the solver-rs directory and get its output (last line only):
-    import subprocess
-    process = subprocess.run(['cargo', 'run'], cwd='../solver-rs', capture_output=True, text=True)
-    errors = process.stderr.strip()
-    print('errors:', errors)
-    solution = process.stdout.strip()
+        print('Trying to build the generated code...')
+        build = json.loads(run_solver(['build', attempt_id]))
+        if not build['success']:
+            print('errors:', build['rendered'] or build['stderr'])
+            return 'no idea'
+        
+        print('Trying to run the generated code...')
+        execution = json.loads(run_solver(['run', attempt_id]))
+        print('errors:', execution['stderr'].strip())
+        solution = execution['stdout'].strip()
+    finally:
+        run_solver(['remove', attempt_id])
     
     print('Rust output:', solution)
     
@@ -173,6 +176,11 @@ def solve_advent_of_code(description, input_text, prompt):
     
     return solution
 
+def run_solver(args, stdin=None):
+    import subprocess
+    process = subprocess.run(['cargo', 'run', '--release', '--quiet', '--'] + args, cwd='../solver-rs', input=stdin, capture_output=True, text=True, check=True)
+    return process.stdout
+
 def call_ollama(prompt):
     request = {
         'model': model,
@@ -0,0 +1,341 @@
+//! Every attempt at solving a task is a crate of its own under `attempts/<id>`, so several tasks
+//! (or several candidate solutions of one task) can be solved at the same time. All attempts are
+//! built into one shared target directory that stays warm between attempts.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use sandbox::{Build, Execution, Limits, INPUT_FILE};
+
+/// Starting point handed to the LLM: reads `input.txt` and prints the result.
+pub const TEMPLATE: &str = include_str!("main_template.rs");
+
+const ATTEMPTS_DIR: &str = "attempts";
+const SOURCE_FILE: &str = "src/main.rs";
+
+/// The attempts of one solver directory.
+#[derive(Debug, Clone)]
+pub struct Solver {
+    dir: PathBuf,
+}
+
+/// One crate with its source and input.
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    pub id: String,
+    dir: PathBuf,
+    target_dir: PathBuf,
+}
+
+impl Solver {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The `solver-rs` directory this crate was compiled in.
+    pub fn default_dir() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    }
+
+    /// Creates a new attempt whose source is the template and whose input is empty.
+    pub fn create(&self) -> io::Result<Attempt> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let id = format!(
+            "{millis}-{}-{}",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let attempt = self.attempt(&id)?;
+        fs::create_dir_all(self.dir.join(ATTEMPTS_DIR))?;
+        // Fails if the directory exists, so two attempts never share one.
+        fs::create_dir(&attempt.dir)?;
+        fs::create_dir(attempt.dir.join("src"))?;
+        fs::write(attempt.dir.join("Cargo.toml"), cargo_toml(&id))?;
+        attempt.write_source(TEMPLATE)?;
+        attempt.write_input("")?;
+        Ok(attempt)
+    }
+
+    /// An existing attempt.
+    pub fn open(&self, id: &str) -> io::Result<Attempt> {
+        let attempt = self.attempt(id)?;
+        if !attempt.dir.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("attempt {id} not found"),
+            ));
+        }
+        Ok(attempt)
+    }
+
+    /// The ids of all attempts, oldest first.
+    pub fn list(&self) -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(self.dir.join(ATTEMPTS_DIR)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e),
+        };
+
+        let mut ids = vec![];
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                ids.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        // By the numbers of `create` (milliseconds, process, counter), not as text.
+        ids.sort_by_key(|id| {
+            let numbers: Vec<Option<u128>> = id.split('-').map(|part| part.parse().ok()).collect();
+            (numbers, id.clone())
+        });
+        Ok(ids)
+    }
+
+    fn attempt(&self, id: &str) -> io::Result<Attempt> {
+        // The id becomes part of paths and of the package name.
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid attempt id {id:?}"),
+            ));
+        }
+
+        Ok(Attempt {
+            id: id.to_string(),
+            dir: self.dir.join(ATTEMPTS_DIR).join(id),
+            target_dir: self.dir.join("target").join(ATTEMPTS_DIR),
+        })
+    }
+}
+
+impl Attempt {
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn source(&self) -> io::Result<String> {
+        fs::read_to_string(self.dir.join(SOURCE_FILE))
+    }
+
+    pub fn write_source(&self, source: &str) -> io::Result<()> {
+        fs::write(self.dir.join(SOURCE_FILE), source)
+    }
+
+    pub fn input(&self) -> io::Result<Vec<u8>> {
+        fs::read(self.dir.join(INPUT_FILE))
+    }
+
+    pub fn write_input(&self, input: &str) -> io::Result<()> {
+        fs::write(self.dir.join(INPUT_FILE), input)
+    }
+
+    /// Builds the attempt into the shared target directory. The binary of a previous build is
+    /// removed first, so a failed build cannot leave an outdated one behind.
+    pub fn build(&self, timeout: Duration) -> io::Result<Build> {
+        remove_file_if_exists(&self.binary())?;
+        sandbox::build_with_target_dir(&self.dir, Some(&self.target_dir), timeout)
+    }
+
+    /// Runs the built binary in the sandbox with the input of the attempt.
+    pub fn run(&self, limits: &Limits) -> io::Result<Execution> {
+        let binary = self.binary();
+        if !binary.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("attempt {} has not been built", self.id),
+            ));
+        }
+        sandbox::run(&binary, &self.input()?, limits)
+    }
+
+    /// Removes the crate and its build artifacts.
+    pub fn remove(self) -> io::Result<()> {
+        fs::remove_dir_all(&self.dir)?;
+
+        let release_dir = self.target_dir.join("release");
+        let package = package_name(&self.id);
+        let crate_name = package.replace('-', "_");
+        remove_file_if_exists(&self.binary())?;
+        remove_file_if_exists(&release_dir.join(format!("{package}.d")))?;
+        remove_matching(&release_dir.join("deps"), &format!("{crate_name}-"))?;
+        remove_matching(&release_dir.join(".fingerprint"), &format!("{package}-"))
+    }
+
+    fn binary(&self) -> PathBuf {
+        self.target_dir.join("release").join(package_name(&self.id))
+    }
+}
+
+fn package_name(id: &str) -> String {
+    format!("attempt-{id}")
+}
+
+/// The empty `[workspace]` keeps cargo from looking for a workspace in the parent directories.
+fn cargo_toml(id: &str) -> String {
+    format!(
+        r#"[package]
+name = "{}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+
+[workspace]
+"#,
+        package_name(id)
+    )
+}
+
+fn remove_file_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Removes the files and directories in `dir` whose name is `prefix` followed by the hash cargo
+/// appends (and maybe an extension), but not the ones of another attempt whose id starts with the
+/// same characters.
+fn remove_matching(dir: &Path, prefix: &str) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    for entry in entries {
+        let entry = entry?;
+        if !is_artifact_of(&entry.file_name().to_string_lossy(), prefix) {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            fs::remove_dir_all(entry.path())?;
+        } else {
+            remove_file_if_exists(&entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+fn is_artifact_of(name: &str, prefix: &str) -> bool {
+    let Some(rest) = name.strip_prefix(prefix) else {
+        return false;
+    };
+    let hash = rest.split('.').next().unwrap_or_default();
+    !hash.is_empty() && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    /// A solver in a fresh temporary directory, one per test.
+    fn solver(test: &str) -> Solver {
+        let dir = env::temp_dir().join(format!("solver-{test}-{}", process::id()));
+        fs::remove_dir_all(&dir).ok();
+        Solver::new(dir)
+    }
+
+    #[test]
+    fn rejects_invalid_ids() {
+        let solver = solver("invalid-ids");
+        for id in ["", "..", "a/b", "../a", "/tmp", "a b"] {
+            let error = solver.open(id).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{id:?}");
+        }
+        assert_eq!(
+            solver.open("1-2-3").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn lists_oldest_first() {
+        let solver = solver("list");
+        assert!(solver.list().unwrap().is_empty());
+
+        // Numbers of different lengths do not sort as text.
+        for id in ["200-1-0", "1000-1-0", "200-1-10", "200-1-9", "99-2-0"] {
+            fs::create_dir_all(solver.dir.join(ATTEMPTS_DIR).join(id)).unwrap();
+        }
+        fs::write(solver.dir.join(ATTEMPTS_DIR).join("300-1-0"), "").unwrap();
+        assert_eq!(
+            solver.list().unwrap(),
+            ["99-2-0", "200-1-0", "200-1-9", "200-1-10", "1000-1-0"]
+        );
+
+        fs::remove_dir_all(&solver.dir).unwrap();
+    }
+
+    #[test]
+    fn creates_and_opens_attempts() {
+        let solver = solver("create");
+        let attempt = solver.create().unwrap();
+        let other = solver.create().unwrap();
+        assert_ne!(attempt.id, other.id);
+
+        let opened = solver.open(&attempt.id).unwrap();
+        assert_eq!(opened.source().unwrap(), TEMPLATE);
+        assert!(opened.input().unwrap().is_empty());
+        assert_eq!(solver.list().unwrap(), [attempt.id, other.id]);
+
+        fs::remove_dir_all(&solver.dir).unwrap();
+    }
+
+    #[test]
+    fn removes_only_own_artifacts() {
+        let solver = solver("remove");
+        fs::create_dir_all(solver.dir.join(ATTEMPTS_DIR)).unwrap();
+        // The second id starts with the first one.
+        let ids = ["1-2", "1-2-3"];
+        let release_dir = solver.dir.join("target").join(ATTEMPTS_DIR).join("release");
+        let artifacts = |id: &str| {
+            let package = package_name(id);
+            let crate_name = package.replace('-', "_");
+            [
+                release_dir.join(&package),
+                release_dir.join(format!("{package}.d")),
+                release_dir
+                    .join("deps")
+                    .join(format!("{crate_name}-536024c71bbe4af4")),
+                release_dir
+                    .join("deps")
+                    .join(format!("{crate_name}-536024c71bbe4af4.d")),
+                release_dir
+                    .join(".fingerprint")
+                    .join(format!("{package}-536024c71bbe4af4")),
+            ]
+        };
+        for id in ids {
+            fs::create_dir(solver.dir.join(ATTEMPTS_DIR).join(id)).unwrap();
+            for artifact in artifacts(id) {
+                fs::create_dir_all(artifact.parent().unwrap()).unwrap();
+                fs::write(artifact, "").unwrap();
+            }
+        }
+
+        solver.open(ids[0]).unwrap().remove().unwrap();
+
+        assert_eq!(solver.list().unwrap(), [ids[1]]);
+        assert!(artifacts(ids[0]).iter().all(|artifact| !artifact.exists()));
+        assert!(artifacts(ids[1]).iter().all(|artifact| artifact.exists()));
+
+        fs::remove_dir_all(&solver.dir).unwrap();
+    }
+}
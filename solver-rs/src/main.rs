@@ -1,10 +1,96 @@
-use std::fs;
+use std::{
+    env,
+    error::Error,
+    fs,
+    io::{self, Read},
+    time::Duration,
+};
 
-fn main() {
-    let _input_string = fs::read_to_string("input.txt").unwrap();
-    let result = "";
+use sandbox::Limits;
+use solver::{Attempt, Solver};
 
-    // your code here
+const USAGE: &str = "usage: solver-rs new
+       solver-rs list
+       solver-rs source <attempt> [<source file>]
+       solver-rs input <attempt> [<input file>]
+       solver-rs build [--compact] [--timeout-secs <n>] <attempt>
+       solver-rs run <attempt>
+       solver-rs remove <attempt>";
 
-    println!("{result}");
+const DEFAULT_BUILD_TIMEOUT: Duration = Duration::from_secs(120);
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let solver = Solver::new(Solver::default_dir());
+
+    match args.as_slice() {
+        ["new"] => println!("{}", solver.create()?.id),
+        ["list"] => {
+            for id in solver.list()? {
+                println!("{id}");
+            }
+        }
+        ["source", id, file @ ..] if file.len() <= 1 => {
+            solver.open(id)?.write_source(&read_file_or_stdin(file)?)?;
+        }
+        ["input", id, file @ ..] if file.len() <= 1 => {
+            solver.open(id)?.write_input(&read_file_or_stdin(file)?)?;
+        }
+        ["build", args @ ..] => build(&solver, args)?,
+        ["run", id] => {
+            let execution = solver.open(id)?.run(&Limits::default())?;
+            println!("{}", serde_json::to_string_pretty(&execution)?);
+        }
+        ["remove", id] => solver.open(id)?.remove()?,
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+/// Reads the file, or stdin if there is none.
+fn read_file_or_stdin(file: &[&str]) -> io::Result<String> {
+    match file {
+        [file] => fs::read_to_string(file),
+        _ => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            Ok(text)
+        }
+    }
+}
+
+/// Prints the build result as JSON, or with `--compact` only the diagnostics, one per line.
+fn build(solver: &Solver, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut compact = false;
+    let mut timeout = DEFAULT_BUILD_TIMEOUT;
+    let mut attempt: Option<Attempt> = None;
+
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "--compact" => compact = true,
+            "--timeout-secs" => {
+                timeout = Duration::from_secs(args.next().ok_or(USAGE)?.parse()?);
+            }
+            _ if attempt.is_none() && !arg.starts_with("--") => attempt = Some(solver.open(arg)?),
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    let build = attempt.ok_or(USAGE)?.build(timeout)?;
+    if !compact {
+        println!("{}", serde_json::to_string_pretty(&build)?);
+        return Ok(());
+    }
+
+    for diagnostic in &build.diagnostics {
+        println!("{diagnostic}");
+    }
+    if build.timed_out {
+        println!("error: build timed out");
+    } else if !build.success && build.diagnostics.is_empty() {
+        print!("{}", build.stderr);
+    }
+    Ok(())
 }